@@ -0,0 +1,148 @@
+use std::process;
+
+use crate::deets;
+use crate::hwmon;
+use crate::modules;
+
+pub enum Mode {
+    Gui,
+    Tty,
+    Stdout,
+    I3bar,
+    Waybar(String),
+    CheckConfig,
+}
+
+pub struct Args {
+    pub config: Option<String>,
+    pub mode:   Mode,
+}
+
+const USAGE: &str = "Usage: randy [OPTIONS] [CONFIG]
+
+Options:
+  -c, --config PATH         use this config file instead of searching for one
+      --check-config        check the config and exit non-zero on errors
+      --tty                 draw the ui in the terminal instead of a window
+      --stdout              print plain text frames to stdout
+      --i3bar               speak the i3bar/swaybar JSON protocol on stdout
+      --waybar ID[,ID...]   print Waybar custom module JSON for the given item ids
+      --print-default-config
+                            print the default config, a good place to start
+      --list-funcs          list every func a `system` item can use
+      --list-modules        list every module type and its keys
+      --list-sensors        list every hwmon sensor and thermal zone with its current value
+  -V, --version             print the version
+  -h, --help                print this help";
+
+fn fail(msg: &str) -> ! {
+    eprintln!("{}\n\n{}", msg, USAGE);
+    process::exit(2);
+}
+
+fn list_funcs() {
+    for func in deets::FUNCS {
+        let feature = match func.feature {
+            Some(f) if !deets::feature_enabled(f) => format!(" (needs --features {})", f),
+            _ => String::new(),
+        };
+
+        println!("{:<18}{}{}", func.name, func.about, feature);
+        if func.required.len() != 0 {
+            println!("{:<18}required: {}", "", func.required.join(", "));
+        }
+    }
+}
+
+fn list_modules() {
+    fn keys(keys: &[&str], required: &[&str]) -> String {
+        return keys.iter()
+            .map(|k| match required.contains(k) { true => format!("{}*", k), false => String::from(*k) })
+            .collect::<Vec<String>>()
+            .join(", ");
+    }
+
+    for (name, spec) in modules::specs() {
+        println!("{:<18}{}", name, spec.about);
+        if spec.keys.len() != 0 {
+            println!("{:<18}keys: {}", "", keys(spec.keys, spec.required));
+        }
+        if spec.item_keys.len() != 0 {
+            println!("{:<18}item keys: {}", "", keys(spec.item_keys, spec.item_required));
+        }
+    }
+
+    println!("\n* required, `text` is required on every module");
+}
+
+fn list_sensors() {
+    let sensors = hwmon::list();
+    if sensors.len() == 0 {
+        eprintln!("No sensors found in /sys/class/hwmon or /sys/class/thermal");
+        return;
+    }
+
+    println!("{:<18}{:<24}{}", "sensor_name", "label_name", "value");
+    for sensor in sensors {
+        let value = match sensor.value() {
+            Some(v) => format!("{:.*}{}", sensor.kind.precision(), v, sensor.kind.unit()),
+            None => String::from("unknown"),
+        };
+
+        println!("{:<18}{:<24}{}", sensor.chip, sensor.label, value);
+    }
+}
+
+// Handles the options that just print something and exit right here
+pub fn parse() -> Args {
+    let mut args = Args { config: None, mode: Mode::Gui };
+    let mut iter = std::env::args().skip(1);
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-c" | "--config" => match iter.next() {
+                Some(path) => args.config = Some(path),
+                None => fail("--config needs a path"),
+            },
+            "--check-config" => args.mode = Mode::CheckConfig,
+            "--tty"          => args.mode = Mode::Tty,
+            "--stdout"       => args.mode = Mode::Stdout,
+            "--i3bar"        => args.mode = Mode::I3bar,
+            "--waybar" => match iter.next() {
+                Some(ids) => args.mode = Mode::Waybar(ids),
+                None => fail("--waybar needs one or more item ids"),
+            },
+            "--print-default-config" => {
+                print!("{}", include_str!("../config/default.yml"));
+                process::exit(0);
+            },
+            "--list-funcs" => {
+                list_funcs();
+                process::exit(0);
+            },
+            "--list-modules" => {
+                list_modules();
+                process::exit(0);
+            },
+            "--list-sensors" => {
+                list_sensors();
+                process::exit(0);
+            },
+            "-V" | "--version" => {
+                println!("randy {}", env!("CARGO_PKG_VERSION"));
+                process::exit(0);
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            },
+            s if s.starts_with('-') => fail(&format!("Unknown option: {}", s)),
+            _ => match args.config {
+                None => args.config = Some(arg),
+                Some(_) => fail(&format!("Unexpected argument: {}", arg)),
+            },
+        }
+    }
+
+    return args;
+}
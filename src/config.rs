@@ -0,0 +1,568 @@
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::{Marker, TScalarStyle};
+use yaml_rust::Yaml;
+
+use crate::deets;
+use crate::modules::{self, Spec};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Str,
+    Int,
+    // frame counts that get divided by and seconds between frames
+    Positive,
+    Float,
+    Bool,
+}
+
+const SETTINGS: &[(&str, Kind)] = &[
+    ("bar_height",       Kind::Str),
+    ("base_opacity",     Kind::Float),
+    ("color_background", Kind::Str),
+    ("color_bar",        Kind::Str),
+    ("color_bar_high",   Kind::Str),
+    ("color_bar_med",    Kind::Str),
+    ("color_borders",    Kind::Str),
+    ("color_label",      Kind::Str),
+    ("color_text",       Kind::Str),
+    ("color_trough",     Kind::Str),
+    ("decoration",       Kind::Bool),
+    ("font_family",      Kind::Str),
+    ("font_size",        Kind::Str),
+    ("font_size_top",    Kind::Str),
+    ("graph_height",     Kind::Int),
+    ("hot_reload",       Kind::Bool),
+    ("ipc_socket",       Kind::Str),
+    ("metrics_address",  Kind::Str),
+    ("metrics_top",      Kind::Int),
+    ("mod_bat",          Kind::Positive),
+    ("mod_fs",           Kind::Positive),
+    ("mod_policy",       Kind::Positive),
+    ("mod_top",          Kind::Positive),
+    ("resizable",        Kind::Bool),
+    ("skip_taskbar",     Kind::Bool),
+    ("timeout",          Kind::Positive),
+    ("tty_width",        Kind::Int),
+    ("xpos",             Kind::Int),
+    ("ypos",             Kind::Int),
+];
+
+// keys that aren't plain strings, wherever they show up in `ui:`
+const UI_KINDS: &[(&str, Kind)] = &[
+    ("ac",        Kind::Bool),
+    ("freq_bar",  Kind::Bool),
+    ("full",      Kind::Bool),
+    ("governor",  Kind::Bool),
+    ("high",      Kind::Float),
+    ("history",   Kind::Int),
+    ("idx",       Kind::Int),
+    ("limit",     Kind::Positive),
+    ("max",       Kind::Float),
+    ("med",       Kind::Float),
+    ("peak",      Kind::Bool),
+    ("per_row",   Kind::Int),
+    ("precision", Kind::Int),
+    ("softirqs",  Kind::Bool),
+    ("split",     Kind::Bool),
+    ("swap",      Kind::Bool),
+    ("topology",  Kind::Bool),
+    ("totals",    Kind::Bool),
+    ("whole",     Kind::Bool),
+    ("width",     Kind::Positive),
+    ("zram",      Kind::Bool),
+];
+
+// keys that only take one of a few values, wherever they show up in `ui:`
+const CHOICES: &[(&str, &[&str])] = &[
+    ("align",    &["left", "right"]),
+    ("field",    &deets::CPU_TIMES),
+    ("prefix",   &["si", "iec"]),
+    ("resource", &["cpu", "memory", "io"]),
+    ("scale",    &["fixed", "auto", "log"]),
+    ("stall",    &["some", "full"]),
+    ("style",    &["rows", "heatmap"]),
+    ("units",    &["bytes", "bits"]),
+    ("used",     &["available", "free", "cache"]),
+    ("widget",   &["bar", "graph"]),
+];
+
+pub struct Problem {
+    pub line:  usize,
+    pub col:   usize,
+    pub msg:   String,
+    pub fatal: bool,
+}
+
+// Just enough of a Yaml tree to remember where everything came from
+enum Node {
+    Map(Vec<(String, Marker, Node)>, Marker),
+    Seq(Vec<Node>, Marker),
+    Scalar(Yaml, Marker),
+}
+
+impl Node {
+    fn mark(&self) -> Marker {
+        return match self {
+            Node::Map(_, m) | Node::Seq(_, m) | Node::Scalar(_, m) => *m,
+        };
+    }
+
+    fn get(&self, key: &str) -> Option<&Node> {
+        return match self {
+            Node::Map(entries, _) => entries.iter().find(|(k, _, _)| k == key).map(|(_, _, v)| v),
+            _ => None,
+        };
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        return match self {
+            Node::Scalar(Yaml::String(s), _) => Some(s),
+            _ => None,
+        };
+    }
+}
+
+enum Partial {
+    Map(Vec<(String, Marker, Node)>, Marker, Option<(String, Marker)>),
+    Seq(Vec<Node>, Marker),
+}
+
+struct Builder {
+    stack: Vec<Partial>,
+    root:  Option<Node>,
+}
+
+impl Builder {
+    fn insert(&mut self, node: Node) {
+        match self.stack.last_mut() {
+            Some(Partial::Seq(items, _)) => items.push(node),
+            Some(Partial::Map(entries, _, key)) => match key.take() {
+                Some((k, m)) => entries.push((k, m, node)),
+                None => {
+                    let k = match &node {
+                        Node::Scalar(Yaml::String(s), _) => s.clone(),
+                        Node::Scalar(Yaml::Integer(i), _) => format!("{}", i),
+                        Node::Scalar(Yaml::Boolean(b), _) => format!("{}", b),
+                        _ => String::from("?"),
+                    };
+                    *key = Some((k, node.mark()));
+                },
+            },
+            None => {
+                if self.root.is_none() {
+                    self.root = Some(node);
+                }
+            },
+        }
+    }
+}
+
+impl MarkedEventReceiver for Builder {
+    fn on_event(&mut self, ev: Event, mark: Marker) {
+        match ev {
+            Event::MappingStart(_)  => self.stack.push(Partial::Map(Vec::new(), mark, None)),
+            Event::SequenceStart(_) => self.stack.push(Partial::Seq(Vec::new(), mark)),
+            Event::MappingEnd | Event::SequenceEnd => {
+                let node = match self.stack.pop() {
+                    // yaml-rust marks a block mapping at its first `:`, the first key reads better
+                    Some(Partial::Map(entries, m, _)) => {
+                        let m = entries.first().map(|(_, key_mark, _)| *key_mark).unwrap_or(m);
+                        Node::Map(entries, m)
+                    },
+                    Some(Partial::Seq(items, m)) => Node::Seq(items, m),
+                    None => return,
+                };
+                self.insert(node);
+            },
+            Event::Scalar(v, style, _, _) => {
+                let val = match style {
+                    TScalarStyle::Plain => Yaml::from_str(&v),
+                    _ => Yaml::String(v),
+                };
+                self.insert(Node::Scalar(val, mark));
+            },
+            Event::Alias(_) => self.insert(Node::Scalar(Yaml::Null, mark)),
+            _ => (),
+        }
+    }
+}
+
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut curr = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = match ca == *cb { true => 0, false => 1 };
+            curr.push((prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1));
+        }
+        prev = curr;
+    }
+
+    return prev[b.len()];
+}
+
+fn suggest(word: &str, candidates: &[&str]) -> String {
+    let best = candidates.iter()
+        .map(|c| (distance(word, c), *c))
+        .filter(|(d, c)| *d <= 2.max(c.len() / 3))
+        .min_by_key(|(d, _)| *d);
+
+    return match best {
+        Some((_, c)) => format!(", did you mean `{}`?", c),
+        None => String::new(),
+    };
+}
+
+fn kind_ok(node: &Node, kind: Kind) -> bool {
+    return match (node, kind) {
+        (Node::Scalar(Yaml::String(_), _), Kind::Str) => true,
+        (Node::Scalar(Yaml::Integer(_), _), Kind::Int) => true,
+        (Node::Scalar(Yaml::Integer(i), _), Kind::Positive) => *i >= 1,
+        (Node::Scalar(Yaml::Integer(_), _), Kind::Float) => true,
+        (Node::Scalar(Yaml::Real(_), _), Kind::Float) => true,
+        (Node::Scalar(Yaml::Boolean(_), _), Kind::Bool) => true,
+        _ => false,
+    };
+}
+
+fn kind_name(kind: Kind) -> &'static str {
+    return match kind {
+        Kind::Str      => "a string",
+        Kind::Int      => "a whole number",
+        Kind::Positive => "a whole number of at least 1",
+        Kind::Float    => "a number",
+        Kind::Bool     => "true or false",
+    };
+}
+
+struct Checker {
+    problems: Vec<Problem>,
+}
+
+impl Checker {
+    fn error(&mut self, mark: Marker, msg: String) {
+        self.problems.push(Problem { line: mark.line(), col: mark.col() + 1, msg: msg, fatal: true });
+    }
+
+    fn warn(&mut self, mark: Marker, msg: String) {
+        self.problems.push(Problem { line: mark.line(), col: mark.col() + 1, msg: msg, fatal: false });
+    }
+
+    fn check_kind(&mut self, key: &str, node: &Node, kind: Kind) {
+        if !kind_ok(node, kind) {
+            self.error(node.mark(), format!("`{}` should be {}", key, kind_name(kind)));
+        }
+    }
+
+    fn check_keys(&mut self, node: &Node, what: &str, allowed: &[&str], required: &[&str]) {
+        let entries = match node {
+            Node::Map(entries, _) => entries,
+            _ => {
+                self.error(node.mark(), format!("{} should be a mapping", what));
+                return;
+            },
+        };
+
+        for (key, mark, val) in entries {
+            if !allowed.contains(&key.as_str()) {
+                self.warn(*mark, format!("unknown key `{}` in {}{}", key, what, suggest(key, allowed)));
+            }
+
+            match UI_KINDS.iter().find(|(k, _)| k == key) {
+                Some((_, kind)) => self.check_kind(key, val, *kind),
+                None => (),
+            }
+
+            match CHOICES.iter().find(|(k, _)| k == key) {
+                Some((_, choices)) => match val.as_str() {
+                    Some(s) if choices.contains(&s) => (),
+                    _ => self.error(val.mark(), format!("`{}` should be one of: {}", key, choices.join(", "))),
+                },
+                None => (),
+            }
+        }
+
+        for key in required {
+            if node.get(key).is_none() {
+                self.error(node.mark(), format!("{} is missing `{}`", what, key));
+            }
+        }
+    }
+
+    fn check_settings(&mut self, settings: &Node) {
+        let entries = match settings {
+            Node::Map(entries, _) => entries,
+            Node::Scalar(Yaml::Null, _) => return,
+            _ => {
+                self.error(settings.mark(), String::from("`settings` should be a mapping"));
+                return;
+            },
+        };
+
+        let names: Vec<&str> = SETTINGS.iter().map(|(k, _)| *k).collect();
+        for (key, mark, val) in entries {
+            match SETTINGS.iter().find(|(k, _)| k == key) {
+                Some((_, kind)) => self.check_kind(key, val, *kind),
+                None => self.warn(*mark, format!("unknown setting `{}`{}", key, suggest(key, &names))),
+            }
+        }
+    }
+
+    fn check_system_item(&mut self, item: &Node) {
+        let func_node = match item.get("func") {
+            Some(f) => f,
+            None => return,
+        };
+
+        let func = match func_node.as_str() {
+            Some(f) => f,
+            None => {
+                self.error(func_node.mark(), String::from("`func` should be a string"));
+                return;
+            },
+        };
+
+        let names: Vec<&str> = deets::FUNCS.iter().map(|f| f.name).collect();
+        let spec = match deets::FUNCS.iter().find(|f| f.name == func) {
+            Some(s) => s,
+            None => {
+                self.error(func_node.mark(), format!("unknown func `{}`{}", func, suggest(func, &names)));
+                return;
+            },
+        };
+
+        match spec.feature {
+            Some(feature) if !deets::feature_enabled(feature) => {
+                self.warn(func_node.mark(), format!("func `{}` needs randy built with --features {}, it will show \"unimpl\"", func, feature));
+            },
+            _ => (),
+        }
+
+        for key in spec.required {
+            if item.get(key).is_none() {
+                self.error(item.mark(), format!("func `{}` is missing `{}`", func, key));
+            }
+        }
+    }
+
+    fn check_columns(&mut self, node: &Node, what: &str) {
+        let entries = match node {
+            Node::Seq(entries, _) => entries,
+            _ => {
+                self.error(node.mark(), format!("`columns` of {} should be a list", what));
+                return;
+            },
+        };
+
+        if entries.is_empty() {
+            self.warn(node.mark(), format!("`columns` of {} is empty, the default columns are shown", what));
+        }
+
+        let names = modules::consumer_columns();
+        let col_what = format!("{} column", what);
+        for entry in entries {
+            let col = match entry {
+                Node::Map(_, _) => {
+                    self.check_keys(entry, &col_what, &["col", "width", "align"], &["col"]);
+                    entry.get("col")
+                },
+                _ => Some(entry),
+            };
+
+            match col.map(|c| (c, c.as_str())) {
+                Some((_, Some(name))) if names.contains(&name) => (),
+                Some((c, Some(name))) => self.error(c.mark(), format!("unknown column `{}`{}", name, suggest(name, &names))),
+                Some((c, None)) => self.error(c.mark(), format!("a {} should be a column name or a mapping with `col`", col_what)),
+                None => (),
+            }
+        }
+    }
+
+    fn check_module(&mut self, entry: &Node, types: &Vec<(String, Spec)>) {
+        let type_node = match entry.get("type") {
+            Some(t) => t,
+            None => {
+                // no type is just a titled frame
+                self.check_keys(entry, "ui entry", &["text", "type"], &["text"]);
+                return;
+            },
+        };
+
+        let type_name = type_node.as_str().unwrap_or("");
+        let spec = match types.iter().find(|(t, _)| t == type_name) {
+            Some((_, spec)) => spec,
+            None => {
+                let names: Vec<&str> = types.iter().map(|(t, _)| t.as_str()).collect();
+                self.error(type_node.mark(), format!("unknown module type `{}`{}", type_name, suggest(type_name, &names)));
+                return;
+            },
+        };
+
+        let what = format!("`{}` module", type_name);
+        let mut allowed = vec!["text", "type"];
+        allowed.extend_from_slice(spec.keys);
+        let mut required = vec!["text"];
+        required.extend_from_slice(spec.required);
+        self.check_keys(entry, &what, &allowed, &required);
+
+        match entry.get("columns") {
+            Some(columns) if spec.keys.contains(&"columns") => self.check_columns(columns, &what),
+            _ => (),
+        }
+
+        let items = match entry.get("items") {
+            Some(Node::Seq(items, _)) => items,
+            Some(n) => {
+                if spec.keys.contains(&"items") {
+                    self.error(n.mark(), format!("`items` of {} should be a list", what));
+                }
+                return;
+            },
+            None => return,
+        };
+
+        let item_what = format!("{} item", what);
+        for item in items {
+            self.check_keys(item, &item_what, spec.item_keys, spec.item_required);
+            if type_name == "system" {
+                self.check_system_item(item);
+            }
+            if type_name == "diskio" && item.get("device").is_none() && item.get("mount_point").is_none() {
+                self.error(item.mark(), format!("{} needs a `device` or a `mount_point`", item_what));
+            }
+        }
+    }
+}
+
+// Checks the whole config and returns everything that looks wrong with it
+pub fn check(yaml_str: &str) -> Vec<Problem> {
+    let mut checker = Checker { problems: Vec::new() };
+    let mut builder = Builder { stack: Vec::new(), root: None };
+
+    match Parser::new(yaml_str.chars()).load(&mut builder, false) {
+        Ok(_) => (),
+        Err(e) => {
+            checker.error(*e.marker(), format!("invalid YAML: {}", e));
+            return checker.problems;
+        },
+    }
+
+    let root = match builder.root {
+        Some(r @ Node::Map(_, _)) => r,
+        Some(r) => {
+            checker.error(r.mark(), String::from("the config should be a mapping with `settings` and `ui`"));
+            return checker.problems;
+        },
+        None => {
+            checker.problems.push(Problem { line: 1, col: 1, msg: String::from("the config is empty"), fatal: true });
+            return checker.problems;
+        },
+    };
+
+    checker.check_keys(&root, "the config", &["settings", "ui"], &["ui"]);
+
+    match root.get("settings") {
+        Some(settings) => checker.check_settings(settings),
+        None => (),
+    }
+
+    let types = modules::specs();
+    match root.get("ui") {
+        Some(Node::Seq(entries, _)) => {
+            for entry in entries {
+                checker.check_module(entry, &types);
+            }
+        },
+        Some(n) => checker.error(n.mark(), String::from("`ui` should be a list")),
+        None => (),
+    }
+
+    checker.problems.sort_by_key(|p| (p.line, p.col));
+    return checker.problems;
+}
+
+pub fn report(path: &str, problems: &Vec<Problem>) {
+    for p in problems {
+        eprintln!("{}:{}:{}: {}: {}", path, p.line, p.col, match p.fatal { true => "error", false => "warning" }, p.msg);
+    }
+}
+
+pub fn has_errors(problems: &Vec<Problem>) -> bool {
+    return problems.iter().any(|p| p.fatal);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `line:col: message` of each problem, in order
+    fn problems(yaml: &str) -> Vec<String> {
+        return check(yaml).iter().map(|p| format!("{}:{}: {}", p.line, p.col, p.msg)).collect();
+    }
+
+    #[test]
+    fn shipped_config_has_no_errors() {
+        assert!(!has_errors(&check(include_str!("../config/default.yml"))));
+    }
+
+    #[test]
+    fn a_good_config_is_quiet() {
+        let yaml = "settings:\n  timeout: 1\nui:\n  - text: SYSTEM\n    type: system\n    items:\n      - func: uptime\n        text: \"Up:\"\n";
+        assert_eq!(problems(yaml), Vec::<String>::new());
+    }
+
+    #[test]
+    fn unknown_keys_warn_with_a_suggestion() {
+        let p = check("settings:\n  mod_fz: 2\nui: []\n");
+        assert_eq!(p.len(), 1);
+        assert_eq!((p[0].line, p[0].col, p[0].fatal), (2, 3, false));
+        assert_eq!(p[0].msg, "unknown setting `mod_fz`, did you mean `mod_fs`?");
+    }
+
+    #[test]
+    fn wrong_kinds_are_errors_at_the_value() {
+        assert_eq!(problems("settings:\n  timeout: 0\n  decoration: maybe\nui: []\n"), vec![
+            "2:12: `timeout` should be a whole number of at least 1",
+            "3:15: `decoration` should be true or false",
+        ]);
+    }
+
+    #[test]
+    fn modules_items_and_funcs() {
+        let yaml = "ui:\n  - text: A\n    type: sytem\n  - text: B\n    type: system\n    items:\n      - func: uptme\n        text: x\n      - text: y\n";
+        assert_eq!(problems(yaml), vec![
+            "3:11: unknown module type `sytem`, did you mean `system`?",
+            "7:15: unknown func `uptme`, did you mean `uptime`?",
+            "9:9: `system` module item is missing `func`",
+        ]);
+    }
+
+    #[test]
+    fn choices_and_columns() {
+        let yaml = "ui:\n  - text: TOP\n    type: cpu_consumers\n    limit: 0\n    columns: [name, pdi, {col: cpu, align: middle}]\n";
+        let p = check(yaml);
+        assert!(has_errors(&p));
+        assert_eq!(p.iter().map(|p| p.msg.as_str()).collect::<Vec<_>>(), vec![
+            "`limit` should be a whole number of at least 1",
+            "unknown column `pdi`, did you mean `pid`?",
+            "`align` should be one of: left, right",
+        ]);
+    }
+
+    #[test]
+    fn broken_yaml_is_one_error() {
+        let p = check("ui:\n  - text: [\n");
+        assert_eq!(p.len(), 1);
+        assert!(p[0].fatal);
+        assert!(p[0].msg.starts_with("invalid YAML"));
+    }
+
+    #[test]
+    fn empty_and_non_mapping_configs() {
+        assert_eq!(problems(""), vec!["1:1: the config is empty"]);
+        assert_eq!(problems("- a\n"), vec!["1:1: the config should be a mapping with `settings` and `ui`"]);
+        assert_eq!(problems("settings: {}\n"), vec!["1:1: the config is missing `ui`"]);
+    }
+}
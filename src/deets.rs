@@ -1,6 +1,7 @@
 use crate::file_utils::*;
+use crate::hwmon;
 
-use libc::{c_char, c_int, c_ulong};
+use libc::{c_char, c_long, c_ulong};
 
 use std::{str, mem, slice, fs};
 #[cfg(not(feature = "timings"))]
@@ -13,6 +14,7 @@ use std::fs::File;
 use std::io::{BufReader, Seek, SeekFrom};
 use std::process::Command;
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use yaml_rust::{Yaml};
 
@@ -22,17 +24,68 @@ use nvml_wrapper::enum_wrappers::device::{TemperatureSensor};
 use nvml_wrapper::NVML;
 
 pub struct FileSystemUsage {
-    pub used:  f64,
-    pub total: f64,
-    pub used_str: String,
-    pub total_str: String,
-    pub use_pct: String,
+    pub used:  u64,
+    pub total: u64,
 }
 
+// A single reading from do_func, left unformatted.
+// Turning it into text is up to crate::format
+pub enum Deet {
+    Text(String),
+    Count(u64),
+    Percent(f64),
+    Duration(Duration),
+    Loads([f64; 3]),
+    BytesOf(u64, u64),
+    Measure { value: f64, unit: &'static str, precision: usize },
+    Unknown,
+}
+
+impl Deet {
+    // how full a bar showing this reading should be, if it makes sense as one
+    pub fn fraction(&self) -> Option<f64> {
+        return match self {
+            Deet::Percent(p) => Some(p / 100.0),
+            Deet::BytesOf(used, total) => match total {
+                0 => Some(0.0),
+                _ => Some(*used as f64 / *total as f64),
+            },
+            _ => None,
+        };
+    }
+
+    // what a graph of this reading plots, the fraction if it has one
+    pub fn value(&self) -> Option<f64> {
+        match self.fraction() {
+            Some(f) => return Some(f),
+            None => (),
+        }
+
+        return match self {
+            Deet::Count(n) => Some(*n as f64),
+            Deet::Duration(d) => Some(d.as_secs() as f64),
+            Deet::Loads(loads) => Some(loads[0]),
+            Deet::Measure { value, .. } => Some(*value),
+            _ => None,
+        };
+    }
+
+    // the fraction as a percent, 0 to 100
+    pub fn percent(&self) -> Option<f64> {
+        return self.fraction().map(|f| f * 100.0);
+    }
+}
+
+// the columns of a cpu line in /proc/stat, guest time is also counted in user
+pub const CPU_TIMES: [&str; 9] = ["user", "nice", "system", "idle", "iowait", "irq", "softirq", "steal", "guest"];
+
 struct CpuLoad {
     idle:  u64,
     total: u64,
     percent: f64,
+    // the counters as read, and the % of the last frame each one took
+    times:  [u64; 9],
+    shares: [f64; 9],
 }
 
 pub struct PsInfo {
@@ -40,19 +93,204 @@ pub struct PsInfo {
     pub cpu: f32,
     pub mem: f32,
     pub comm: String,
+    // bytes read and written per second, None unless asked for or when /proc/<pid>/io can't be read
+    pub io: Option<(f64, f64)>,
+    pub details: Option<PsDetails>,
+}
+
+// what the /proc/<pid> walk reads on top of cpu and memory use
+#[derive(Clone, Copy, Default)]
+pub struct PsWants {
+    pub io:      bool,
+    pub details: bool,
+}
+
+// which of the optional /proc files get_frame_cache reads, nothing shown means nothing read
+#[derive(Clone, Copy, Default)]
+pub struct CacheWants {
+    // all of /proc/meminfo, the process list gets MemTotal from sysinfo
+    pub meminfo:    bool,
+    pub vmstat:     bool,
+    pub disk_stats: bool,
+    pub pressure:   bool,
+}
+
+impl CacheWants {
+    #[cfg(feature = "prometheus")]
+    pub fn all() -> CacheWants {
+        return CacheWants {
+            meminfo:    true,
+            vmstat:     true,
+            disk_stats: true,
+            pressure:   true,
+        };
+    }
+
+    pub fn or(self, other: CacheWants) -> CacheWants {
+        return CacheWants {
+            meminfo:    self.meminfo || other.meminfo,
+            vmstat:     self.vmstat || other.vmstat,
+            disk_stats: self.disk_stats || other.disk_stats,
+            pressure:   self.pressure || other.pressure,
+        };
+    }
+}
+
+// what a `system` item's func reads from the frame cache
+pub fn func_wants(func: &str) -> CacheWants {
+    return match func {
+        "ram_usage" | "swap_usage" => CacheWants { meminfo: true, ..CacheWants::default() },
+        "page_faults" | "major_faults" | "swap_in" | "swap_out" | "pgscan" | "pgsteal" | "oom_kills" => CacheWants { vmstat: true, ..CacheWants::default() },
+        "pressure" => CacheWants { pressure: true, ..CacheWants::default() },
+        _ => CacheWants::default(),
+    };
+}
+
+pub struct PsDetails {
+    pub user:    String,
+    pub state:   char,
+    pub nice:    i64,
+    pub threads: u64,
+    // bytes
+    pub rss:     u64,
+    pub swap:    u64,
+    // seconds after boot
+    pub start:   u64,
+    // argv[0] without its path, then the arguments
+    pub cmdline: String,
+    // the innermost cgroup, container ids cut short
+    pub cgroup:  String,
+}
+
+pub struct FuncSpec {
+    pub name:     &'static str,
+    pub about:    &'static str,
+    pub required: &'static [&'static str],
+    // the cargo feature randy needs to be built with for this func
+    pub feature:  Option<&'static str>,
+}
+
+// Everything do_func knows about, built with the feature or not
+pub const FUNCS: &[FuncSpec] = &[
+    FuncSpec { name: "hostname",        about: "hostname of this machine",                     required: &[], feature: None },
+    FuncSpec { name: "kernel",          about: "running kernel release",                       required: &[], feature: None },
+    FuncSpec { name: "uptime",          about: "time since boot",                              required: &[], feature: None },
+    FuncSpec { name: "load",            about: "1, 5 and 15 minute load averages",             required: &[], feature: None },
+    FuncSpec { name: "procs_count",     about: "number of running processes",                  required: &[], feature: None },
+    FuncSpec { name: "procs_blocked",   about: "number of processes blocked on I/O",           required: &[], feature: None },
+    FuncSpec { name: "ctxt",            about: "context switches per second",                  required: &[], feature: None },
+    FuncSpec { name: "intr",            about: "interrupts per second",                        required: &[], feature: None },
+    FuncSpec { name: "forks",           about: "processes created per second",                 required: &[], feature: None },
+    FuncSpec { name: "page_faults",     about: "page faults per second",                       required: &[], feature: None },
+    FuncSpec { name: "major_faults",    about: "page faults that needed disk I/O per second",  required: &[], feature: None },
+    FuncSpec { name: "swap_in",         about: "pages swapped in per second",                  required: &[], feature: None },
+    FuncSpec { name: "swap_out",        about: "pages swapped out per second",                 required: &[], feature: None },
+    FuncSpec { name: "pgscan",          about: "pages scanned for reclaim per second",         required: &[], feature: None },
+    FuncSpec { name: "pgsteal",         about: "pages reclaimed per second",                   required: &[], feature: None },
+    FuncSpec { name: "oom_kills",       about: "processes the OOM killer took since boot",     required: &[], feature: None },
+    FuncSpec { name: "ram_usage",       about: "used / total RAM (total - MemAvailable)",      required: &[], feature: None },
+    FuncSpec { name: "swap_usage",      about: "used / total swap",                            required: &[], feature: None },
+    FuncSpec { name: "cpu_usage",       about: "usage of all CPUs together",                   required: &[], feature: None },
+    FuncSpec { name: "cpu_time",        about: "% of CPU time in field (iowait, steal...)",    required: &["field"], feature: None },
+    FuncSpec { name: "cpu_temp_sys",    about: "temperature of thermal_zone0",                 required: &[], feature: None },
+    FuncSpec { name: "cpu_speed_rpi",   about: "ARM clock from vcgencmd (Raspberry Pi)",       required: &[], feature: None },
+    FuncSpec { name: "cpu_voltage_rpi", about: "core voltage from vcgencmd (Raspberry Pi)",    required: &[], feature: None },
+    FuncSpec { name: "nvidia_gpu_temp", about: "temperature of NVIDIA GPU number idx",         required: &["idx"], feature: Some("nvidia") },
+    FuncSpec { name: "hwmon",           about: "a hwmon sensor, see `randy --list-sensors`",   required: &["sensor_name", "label_name"], feature: None },
+    FuncSpec { name: "sensor_info",     about: "lm-sensors value (hwmon if built without it)", required: &["sensor_name", "label_name"], feature: None },
+    FuncSpec { name: "pressure",        about: "PSI stall % of cpu, memory or io",             required: &["resource"], feature: None },
+];
+
+pub fn feature_enabled(feature: &str) -> bool {
+    return match feature {
+        "nvidia"  => cfg!(feature = "nvidia"),
+        "sensors" => cfg!(feature = "sensors"),
+        _ => false,
+    };
+}
+
+// counters from /proc/diskstats, sectors there are always 512 bytes
+#[derive(Clone, Copy)]
+pub struct DiskStat {
+    pub reads: u64,
+    pub writes: u64,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+    // ms spent doing I/O
+    pub io_ticks: u64,
+}
+
+pub struct Zram {
+    pub name: String,
+    // what was swapped out and what that takes after compression, in bytes
+    pub orig: u64,
+    pub compr: u64,
+    pub disksize: u64,
+}
+
+// one line of /proc/pressure/<resource>
+#[derive(Clone, Copy)]
+pub struct PressureLine {
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
+    // % of the time stalled since the last frame, from the total= counter
+    pub rate: f64,
+}
+
+impl PressureLine {
+    // `avg:` is 10, 60, 300 or total
+    pub fn get(&self, avg: &str) -> f64 {
+        return match avg {
+            "60"    => self.avg60,
+            "300"   => self.avg300,
+            "total" => self.rate,
+            _ => self.avg10,
+        };
+    }
+}
+
+pub struct Pressure {
+    pub some: PressureLine,
+    // the cpu resource only has `full` since 5.13
+    pub full: Option<PressureLine>,
+}
+
+// a counter that only goes up, and how fast it went since the last frame
+#[derive(Clone, Copy)]
+pub struct Counter {
+    pub total: u64,
+    // per second
+    pub rate:  f64,
+}
+
+// one line of /proc/interrupts or /proc/softirqs, summed over the CPUs
+pub struct Interrupt {
+    // `45`, `LOC` or `softirq.NET_RX`
+    pub id:    String,
+    pub label: String,
+    pub total: u64,
 }
 
 pub struct FrameCache {
-    pub mem_total: f64,
-    pub mem_free: f64,
-    pub net_dev: HashMap<String, (u64, u64)>,
+    // every /proc/meminfo line, kB values turned into bytes
+    pub meminfo: HashMap<String, u64>,
+    // ctxt, intr and processes (forks) from /proc/stat
+    pub activity: HashMap<String, Counter>,
+    // the VMSTAT_FIELDS of /proc/vmstat
+    pub vmstat: HashMap<String, Counter>,
+    // up and down
+    pub net_dev: HashMap<String, (Counter, Counter)>,
+    pub disk_stats: HashMap<String, DiskStat>,
+    // empty when the kernel has PSI disabled
+    pub pressure: HashMap<String, Pressure>,
     pub ps_info: Vec<PsInfo>,
     proc_stat: Vec<String>,
     sysinfo: libc::sysinfo,
     utsname: libc::utsname,
 }
 
-const LOAD_SHIFT_F32: f32 = (1 << libc::SI_LOAD_SHIFT) as f32;
+const LOAD_SHIFT_F64: f64 = (1 << libc::SI_LOAD_SHIFT) as f64;
 
 // 1000000
 #[cfg(not(feature = "timings"))]
@@ -65,12 +303,21 @@ lazy_static! {
     static ref PROC_LOAD_HIST: Mutex<HashMap<u32, (f64, f64)>> = Mutex::new(HashMap::new());
     static ref PROC_PID_FILES: Mutex<HashMap<String, BufReader<File>>> = Mutex::new(HashMap::new());
     static ref PROC_STAT_READERS: Mutex<HashMap<u32, BufReader<File>>> = Mutex::new(HashMap::new());
+    static ref CPU_FREQ_READERS: Mutex<HashMap<i32, BufReader<File>>> = Mutex::new(HashMap::new());
+    static ref PROC_IO_HIST:   Mutex<HashMap<u32, (u64, u64, Instant)>> = Mutex::new(HashMap::new());
+    static ref USER_NAMES:     HashMap<u32, String> = get_user_names();
+    pub static ref CLK_TCK:    u64 = unsafe { libc::sysconf(libc::_SC_CLK_TCK) } as u64;
     static ref MOUNTS_READER:  Mutex<BufReader<File>> = Mutex::new(BufReader::new(File::open("/proc/mounts").unwrap()));
+    static ref MEMINFO_READER: Mutex<BufReader<File>> = Mutex::new(BufReader::new(File::open("/proc/meminfo").unwrap()));
+    // only the resources the kernel has, none without PSI
+    static ref PRESSURE_READERS: Mutex<Vec<(&'static str, BufReader<File>)>> = Mutex::new(["cpu", "memory", "io"].iter()
+        .filter_map(|r| File::open(format!("/proc/pressure/{}", r)).ok().map(|f| (*r, BufReader::new(f))))
+        .collect());
+    static ref VMSTAT_READER:  Mutex<Option<BufReader<File>>> = Mutex::new(File::open("/proc/vmstat").ok().map(BufReader::new));
+    static ref DISKSTATS_READER: Mutex<Option<BufReader<File>>> = Mutex::new(File::open("/proc/diskstats").ok().map(BufReader::new));
     static ref CPU_INFO_FILE:  Mutex<File> = Mutex::new(File::open("/proc/cpuinfo").unwrap());
-    static ref BATTERY_CACHE:  Mutex<HashMap<String, (BufReader<File>, BufReader<File>)>> = Mutex::new(HashMap::new());
-
-    pub static ref CPU_COUNT: i32 = get_match_strings_from_path("/proc/cpuinfo", &vec!["processor"]).len() as i32;
-    pub static ref CPU_COUNT_FLOAT: f64 = *CPU_COUNT as f64;
+    static ref PSI_TOTALS:     Mutex<HashMap<String, (u64, Instant)>> = Mutex::new(HashMap::new());
+    static ref COUNTER_TOTALS: Mutex<HashMap<String, (u64, Instant)>> = Mutex::new(HashMap::new());
 }
 
 #[cfg(feature = "nvidia")]
@@ -78,9 +325,9 @@ lazy_static! {
     static ref NVML_O:         Mutex<nvml_wrapper::NVML> = Mutex::new(NVML::init().unwrap());
 }
 
-fn get_hostname_from_utsname(n: [c_char; 65]) -> String {
+fn get_hostname_from_utsname(n: [c_char; 65]) -> Deet {
     let hostname: &[u8] = unsafe{ slice::from_raw_parts(n.as_ptr() as *const u8, n.len()) };
-    return str_from_bytes(hostname.to_vec());
+    return Deet::Text(str_from_bytes(hostname.to_vec()));
 }
 
 fn get_utsname() -> libc::utsname {
@@ -89,18 +336,13 @@ fn get_utsname() -> libc::utsname {
     return utsname;
 }
 
-fn get_uname(r: [c_char; 65]) -> String {
+fn get_uname(r: [c_char; 65]) -> Deet {
     let release: &[u8] = unsafe{ slice::from_raw_parts(r.as_ptr() as *const u8, r.len()) };
-    return str_from_bytes(release.to_vec());
+    return Deet::Text(str_from_bytes(release.to_vec()));
 }
 
-fn get_uptime_string(uptime: c_int) -> String {
-    let d = uptime / 60 / 60 / 24;
-    let h = (uptime / 60 / 60) - (d * 24);
-    let m = (uptime / 60) - (h * 60) - ((d * 24) * 60);
-    let s = (uptime) - ((d * 24) * 60 * 60) - (h * 60 * 60) - (m * 60);
-
-    return format!("{}d {}h {:02}m {:02}s", d, h, m, s);
+fn get_uptime(uptime: c_long) -> Deet {
+    return Deet::Duration(Duration::from_secs(uptime as u64));
 }
 
 fn get_sysinfo() -> libc::sysinfo {
@@ -109,39 +351,206 @@ fn get_sysinfo() -> libc::sysinfo {
     return sysinfo;
 }
 
-fn get_load(loads: [c_ulong; 3]) -> String {
-    let mut load_arr: [f32; 3] = [0.0, 0.0, 0.0];
+fn get_load(loads: [c_ulong; 3]) -> Deet {
+    let mut load_arr: [f64; 3] = [0.0, 0.0, 0.0];
 
     for i in 0..3 {
-        load_arr[i] = (loads[i] as f32) / LOAD_SHIFT_F32;
+        load_arr[i] = (loads[i] as f64) / LOAD_SHIFT_F64;
     }
 
-    return format!("{:.2} {:.2} {:.2}", load_arr[0], load_arr[1], load_arr[2]);
+    return Deet::Loads(load_arr);
 }
 
-fn get_procs_count(proc_stat: &Vec<String>) -> String {
+fn get_procs_count(proc_stat: &Vec<String>) -> Deet {
     return match proc_stat.iter().find(|line| { line.starts_with("procs_running") }) {
-        Some(r) => r.replace("procs_running ", ""),
+        Some(r) => Deet::Count(r.replace("procs_running ", "").parse().unwrap()),
         _ => panic!("Couldn't find running procs in /proc/stat"),
     };
 }
 
-fn get_ram_usage() -> (f64, f64)  {
-    fn reduce(i: u64) -> f64 {
-        return (i as f64) / 1024.0 / 1024.0;
+fn get_meminfo() -> HashMap<String, u64> {
+    let reader = &mut MEMINFO_READER.lock().unwrap();
+    return try_strings_from_reader(reader, 128).unwrap().iter().filter_map(|line| parse_meminfo(line)).collect();
+}
+
+// `MemTotal:       16318480 kB`, kB values turned into bytes, HugePages_* are counts
+fn parse_meminfo(line: &str) -> Option<(String, u64)> {
+    let tokens = split_spc_to_strs!(line);
+    if tokens.len() < 2 {
+        return None;
+    }
+
+    let val = tokens[1].parse::<u64>().unwrap_or(0);
+    return Some((String::from(tokens[0].trim_end_matches(':')), match tokens.get(2) {
+        Some(&"kB") => val * 1024,
+        _ => val,
+    }));
+}
+
+impl FrameCache {
+    pub fn mem(&self, key: &str) -> u64 {
+        return *self.meminfo.get(key).unwrap_or(&0);
+    }
+
+    // seconds since boot
+    pub fn uptime(&self) -> u64 {
+        return self.sysinfo.uptime as u64;
+    }
+
+    // kernels older than 3.14 don't have MemAvailable
+    pub fn mem_available(&self) -> u64 {
+        return match self.meminfo.get("MemAvailable") {
+            Some(a) => *a,
+            None => self.mem("MemFree") + self.mem("Buffers") + self.mem("Cached"),
+        };
     }
+}
+
+pub fn get_zram() -> Vec<Zram> {
+    let mut devices: Vec<Zram> = Vec::new();
+
+    let dirs = match fs::read_dir("/sys/block") {
+        Ok(d)  => d,
+        Err(_) => return devices,
+    };
 
-    fn get_item(i: usize, v: &Vec<String>) -> u64 {
-        return v[i]
-            .split_ascii_whitespace().collect::<String>()
-            .split(':').collect::<Vec<&str>>()[1]
-            .replace("kB", "").parse().unwrap();
+    for entry in dirs.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.starts_with("zram") {
+            continue;
+        }
+
+        let path = entry.path();
+        let mm_stat = match fs::read_to_string(path.join("mm_stat")) {
+            Ok(s)  => s,
+            Err(_) => continue,
+        };
+
+        let tokens = split_spc_to_strs!(mm_stat);
+        let num = |i: usize| tokens.get(i).and_then(|t| t.parse::<u64>().ok()).unwrap_or(0);
+        devices.push(Zram {
+            name: name,
+            orig: num(0),
+            compr: num(1),
+            disksize: fs::read_to_string(path.join("disksize")).ok()
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or(0),
+        });
     }
 
-    let meminfo = get_strings_from_path("/proc/meminfo", 3);
-    let free  = reduce(get_item(2, &meminfo));
-    let total = reduce(get_item(0, &meminfo));
-    return (free, total);
+    devices.sort_by(|a, b| a.name.cmp(&b.name));
+    return devices;
+}
+
+// /sys/devices/system/cpu/cpu*/cpufreq, frequencies in MHz
+fn cpufreq_path(cpu: i32, file: &str) -> String {
+    return format!("/sys/devices/system/cpu/cpu{}/cpufreq/{}", cpu, file);
+}
+
+fn read_cpufreq(cpu: i32, file: &str) -> Option<String> {
+    return fs::read_to_string(cpufreq_path(cpu, file)).ok().map(|s| String::from(s.trim()));
+}
+
+fn read_cpufreq_mhz(cpu: i32, file: &str) -> Option<u32> {
+    return read_cpufreq(cpu, file)?.parse::<u32>().ok().map(|khz| khz / 1000);
+}
+
+// what the governor is allowed to do, it only changes when someone changes it
+pub struct CpuPolicy {
+    pub min: u32,
+    pub max: u32,
+    pub governor: String,
+    pub epp: Option<String>,
+}
+
+// the current clock of each CPU, None when there is no cpufreq (VMs, some ARM boards), get_cpu_mhz still works there
+pub fn get_cpu_freqs(cpus: &Vec<i32>) -> Option<HashMap<i32, u32>> {
+    let readers = &mut CPU_FREQ_READERS.lock().unwrap();
+    let mut freqs = HashMap::new();
+
+    for cpu in cpus.iter().cloned() {
+        if !readers.contains_key(&cpu) {
+            readers.insert(cpu, BufReader::new(File::open(cpufreq_path(cpu, "scaling_cur_freq")).ok()?));
+        }
+
+        let khz = try_strings_from_reader(readers.get_mut(&cpu).unwrap(), 1).ok()
+            .and_then(|lines| lines.get(0)?.parse::<u32>().ok());
+        match khz {
+            Some(k) => { freqs.insert(cpu, k / 1000); },
+            // gone offline, the next frame opens it again
+            None => { readers.remove(&cpu); },
+        }
+    }
+
+    return Some(freqs);
+}
+
+// the fastest the hardware goes, scaling max can be lower. Read once, it never changes
+pub fn get_cpu_hw_max(cpus: &Vec<i32>) -> HashMap<i32, u32> {
+    return cpus.iter().filter_map(|cpu| {
+        let max = read_cpufreq_mhz(*cpu, "cpuinfo_max_freq").or(read_cpufreq_mhz(*cpu, "scaling_max_freq"))?;
+        return Some((*cpu, max));
+    }).collect();
+}
+
+pub fn get_cpu_policies(cpus: &Vec<i32>) -> HashMap<i32, CpuPolicy> {
+    return cpus.iter().filter_map(|cpu| {
+        return Some((*cpu, CpuPolicy {
+            min: read_cpufreq_mhz(*cpu, "scaling_min_freq").unwrap_or(0),
+            max: read_cpufreq_mhz(*cpu, "scaling_max_freq")?,
+            governor: read_cpufreq(*cpu, "scaling_governor").unwrap_or(String::from("unknown")),
+            epp: read_cpufreq(*cpu, "energy_performance_preference"),
+        }));
+    }).collect();
+}
+
+pub struct CpuTopo {
+    pub id: i32,
+    pub package: i32,
+    pub core: i32,
+    // "P" or "E" on hybrid CPUs
+    pub kind: Option<&'static str>,
+}
+
+// "0-3,8,10-11" as used all over /sys/devices/system/cpu
+fn parse_cpu_list(list: &str) -> Vec<i32> {
+    let mut cpus = Vec::new();
+
+    for part in list.trim().split(',') {
+        let bounds: Vec<i32> = part.split('-').filter_map(|n| n.parse().ok()).collect();
+        match bounds.len() {
+            1 => cpus.push(bounds[0]),
+            2 => cpus.extend(bounds[0]..=bounds[1]),
+            _ => (),
+        }
+    }
+
+    return cpus;
+}
+
+pub fn get_topology(cpus: &Vec<i32>) -> Vec<CpuTopo> {
+    fn read_id(cpu: i32, file: &str, fallback: i32) -> i32 {
+        return fs::read_to_string(format!("/sys/devices/system/cpu/cpu{}/topology/{}", cpu, file)).ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(fallback);
+    }
+
+    // only there on hybrid Intel CPUs
+    let p_cores = fs::read_to_string("/sys/devices/cpu_core/cpus").map(|s| parse_cpu_list(&s)).unwrap_or(Vec::new());
+    let e_cores = fs::read_to_string("/sys/devices/cpu_atom/cpus").map(|s| parse_cpu_list(&s)).unwrap_or(Vec::new());
+
+    return cpus.iter().map(|cpu| {
+        return CpuTopo {
+            id: *cpu,
+            package: read_id(*cpu, "physical_package_id", 0),
+            core: read_id(*cpu, "core_id", *cpu),
+            kind: match (p_cores.contains(cpu), e_cores.contains(cpu)) {
+                (true, _) => Some("P"),
+                (_, true) => Some("E"),
+                _ => None,
+            },
+        };
+    }).collect();
 }
 
 pub fn get_cpu_mhz() -> Vec<u16> {
@@ -155,11 +564,194 @@ pub fn get_cpu_mhz() -> Vec<u16> {
 }
 
 fn get_proc_stat() -> Vec<String> {
-    return get_match_strings_from_path("/proc/stat", &vec!["cpu", "proc"]);
+    return get_match_strings_from_path("/proc/stat", &vec!["cpu", "proc", "ctxt", "intr"]);
+}
+
+// the rate is against the last call with the same key
+fn counter(totals: &mut HashMap<String, (u64, Instant)>, key: &str, total: u64) -> Counter {
+    let now = Instant::now();
+    let rate = match totals.insert(String::from(key), (total, now)) {
+        Some((last, then)) => {
+            let elapsed = now.duration_since(then).as_secs_f64();
+            match elapsed > 0.0 {
+                true  => total.saturating_sub(last) as f64 / elapsed,
+                false => 0.0,
+            }
+        },
+        None => 0.0,
+    };
+
+    return Counter { total: total, rate: rate };
+}
+
+fn get_activity(proc_stat: &Vec<String>) -> HashMap<String, Counter> {
+    let totals = &mut COUNTER_TOTALS.lock().unwrap();
+    let mut map = HashMap::new();
+
+    for name in &["ctxt", "intr", "processes"] {
+        // intr goes on with a count for every irq, the first one is the sum
+        let total = proc_stat.iter()
+            .find(|line| line.starts_with(&format!("{} ", name)))
+            .and_then(|line| line.split_ascii_whitespace().nth(1))
+            .and_then(|n| n.parse::<u64>().ok());
+
+        match total {
+            Some(t) => { map.insert(String::from(*name), counter(totals, &format!("stat {}", name), t)); },
+            None => (),
+        }
+    }
+
+    return map;
+}
+
+fn get_activity_deet(frame_cache: &FrameCache, name: &str) -> Deet {
+    return match frame_cache.activity.get(name) {
+        Some(c) => Deet::Measure { value: c.rate, unit: "/s", precision: 0 },
+        None => Deet::Unknown,
+    };
+}
+
+// the fields the funcs and the exporter show, with the key their rate is kept under.
+// pgscan and pgsteal are summed over kswapd, direct and khugepaged reclaim
+const VMSTAT_FIELDS: [(&str, &str); 7] = [
+    ("pgfault",    "vmstat pgfault"),
+    ("pgmajfault", "vmstat pgmajfault"),
+    ("pswpin",     "vmstat pswpin"),
+    ("pswpout",    "vmstat pswpout"),
+    ("pgscan",     "vmstat pgscan"),
+    ("pgsteal",    "vmstat pgsteal"),
+    ("oom_kill",   "vmstat oom_kill"),
+];
+
+// pgscan_anon and pgscan_file (5.8+) count the same pages again, older kernels split these per zone
+const VMSTAT_SUMMED: [(&str, &str); 6] = [
+    ("pgscan_kswapd",      "pgscan"),
+    ("pgscan_direct",      "pgscan"),
+    ("pgscan_khugepaged",  "pgscan"),
+    ("pgsteal_kswapd",     "pgsteal"),
+    ("pgsteal_direct",     "pgsteal"),
+    ("pgsteal_khugepaged", "pgsteal"),
+];
+
+fn get_vmstat() -> HashMap<String, Counter> {
+    let reader = &mut VMSTAT_READER.lock().unwrap();
+    let lines = match reader.as_mut() {
+        Some(r) => try_strings_from_reader(r, 512).unwrap_or(Vec::new()),
+        None => Vec::new(),
+    };
+
+    let totals = &mut COUNTER_TOTALS.lock().unwrap();
+    let raw = parse_vmstat(&lines);
+
+    return VMSTAT_FIELDS.iter().filter_map(|(name, key)| {
+        let total = raw.get(name)?;
+        return Some((String::from(*name), counter(totals, key, *total)));
+    }).collect();
+}
+
+// `pgfault 123456` lines, only the VMSTAT_FIELDS are kept
+fn parse_vmstat(lines: &[String]) -> HashMap<&'static str, u64> {
+    let mut raw: HashMap<&'static str, u64> = HashMap::new();
+
+    for line in lines {
+        let mut tokens = line.split_ascii_whitespace();
+        let (name, n) = match (tokens.next(), tokens.next().and_then(|n| n.parse::<u64>().ok())) {
+            (Some(name), Some(n)) => (name, n),
+            _ => continue,
+        };
+
+        match VMSTAT_FIELDS.iter().find(|(field, _)| *field == name) {
+            Some((field, _)) => { raw.insert(field, n); },
+            None => (),
+        }
+
+        if name.ends_with("_throttle") {
+            continue;
+        }
+
+        match VMSTAT_SUMMED.iter().find(|(prefix, _)| name.starts_with(prefix)) {
+            Some((_, sum)) => { *raw.entry(sum).or_insert(0) += n; },
+            None => (),
+        }
+    }
+
+    return raw;
+}
+
+fn get_vmstat_deet(frame_cache: &FrameCache, name: &str, unit: &'static str) -> Deet {
+    return match frame_cache.vmstat.get(name) {
+        Some(c) => Deet::Measure { value: c.rate, unit: unit, precision: 0 },
+        None => Deet::Unknown,
+    };
+}
+
+fn get_procs_blocked(proc_stat: &Vec<String>) -> Deet {
+    return match proc_stat.iter().find(|line| line.starts_with("procs_blocked")) {
+        Some(r) => Deet::Count(r.replace("procs_blocked ", "").parse().unwrap_or(0)),
+        None => Deet::Unknown,
+    };
+}
+
+// The number after the name is the hardware irq, `45:` for example, or a short name like `LOC:` with a description.
+// softirqs are named after the `softirq.` prefix
+pub fn get_interrupts(softirqs: bool) -> Vec<Interrupt> {
+    let mut interrupts = Vec::new();
+
+    let lines = try_strings_from_path("/proc/interrupts", 4096).unwrap_or(Vec::new());
+    let cpus = lines.get(0).map(|l| l.split_ascii_whitespace().count()).unwrap_or(0);
+
+    for line in lines.iter().skip(1) {
+        let tokens: Vec<&str> = line.split_ascii_whitespace().collect();
+        let id = match tokens.get(0) {
+            Some(t) if t.ends_with(':') => &t[..(t.len() - 1)],
+            _ => continue,
+        };
+
+        // ERR and MIS have a single count, not one per CPU
+        let counts: Vec<u64> = tokens[1..].iter()
+            .take(cpus)
+            .take_while(|t| t.parse::<u64>().is_ok())
+            .map(|t| t.parse::<u64>().unwrap())
+            .collect();
+        let desc = &tokens[(1 + counts.len())..];
+
+        // `IR-PCI-MSI 327680-edge nvme0q1`, the devices come after the trigger type
+        let devices = match desc.iter().rposition(|t| t.ends_with("-edge") || t.ends_with("-level") || t.ends_with("-fasteoi") || *t == "Edge" || *t == "Level") {
+            Some(i) if i + 1 < desc.len() => &desc[(i + 1)..],
+            _ => desc,
+        };
+
+        interrupts.push(Interrupt {
+            id:    String::from(id),
+            label: match devices.len() {
+                0 => String::from(id),
+                _ => format!("{} {}", id, devices.join(" ")),
+            },
+            total: counts.iter().sum(),
+        });
+    }
+
+    if softirqs {
+        for line in try_strings_from_path("/proc/softirqs", 64).unwrap_or(Vec::new()).iter().skip(1) {
+            let tokens: Vec<&str> = line.split_ascii_whitespace().collect();
+            let name = match tokens.get(0) {
+                Some(t) if t.ends_with(':') => &t[..(t.len() - 1)],
+                _ => continue,
+            };
+
+            interrupts.push(Interrupt {
+                id:    format!("softirq.{}", name),
+                label: format!("{} (soft)", name),
+                total: tokens[1..].iter().filter_map(|t| t.parse::<u64>().ok()).sum(),
+            });
+        }
+    }
+
+    return interrupts;
 }
 
 #[cfg(feature = "sensors")]
-fn get_sensor_info(sensor_name: &str, label_name: &str, val: &str, whole: bool) -> String {
+fn get_sensor_info(sensor_name: &str, label_name: &str) -> Deet {
     for chip in sensors::Sensors::new() {
         let name = chip.get_name().expect("name");
         if sensor_name == name {
@@ -168,47 +760,86 @@ fn get_sensor_info(sensor_name: &str, label_name: &str, val: &str, whole: bool)
                 if label == label_name {
                     for subfeature in feature {
                         let value = subfeature.get_value().expect("value");
-                        // TODO this beeegs for a proper templating solution
-                        // I think I want to start returning the raw info and
-                        // applying the templates in main ui_update
-
-                        if whole {
-                            return String::from(val).replace("{}", format!("{:.0}", value).as_str());
-                        }
-
-                        return String::from(val).replace("{}", format!("{:.2}", value).as_str());
+                        return Deet::Measure { value: value, unit: "", precision: 2 };
                     }
                 }
             }
         }
     }
 
-    return String::from("unknown");
+    return Deet::Unknown;
+}
+
+fn get_hwmon(sensor_name: &str, label_name: &str) -> Deet {
+    return match hwmon::read(sensor_name, label_name) {
+        Some((value, kind)) => Deet::Measure { value: value, unit: kind.unit(), precision: kind.precision() },
+        None => Deet::Unknown,
+    };
 }
 
-fn get_cpu_temp_sys(val: Option<&str>) -> String {
+fn get_cpu_temp_sys() -> Deet {
     match fs::read_to_string("/sys/class/thermal/thermal_zone0/temp") {
         Ok(s) => {
             match s.trim().parse::<u32>() {
-                Ok(i) => {
-                    match val {
-                        Some(s) => s.replace("{}", format!("{}", (i / 1000)).as_str()),
-                        _ => format!("{}C", (i / 1000)),
-                    }
-                },
-                Err(e) => e.to_string(),
+                Ok(i) => Deet::Measure { value: (i / 1000) as f64, unit: "C", precision: 0 },
+                Err(e) => Deet::Text(e.to_string()),
             }
         },
-        _ => "unknown".to_string(),
+        _ => Deet::Unknown,
     }
 }
 
-fn get_ps_from_proc(counter: u64, mod_top: u64, mem_used: f64) -> Vec<PsInfo> {
+// uid to name from /etc/passwd, users from LDAP and the like show as their uid
+fn get_user_names() -> HashMap<u32, String> {
+    return fs::read_to_string("/etc/passwd").unwrap_or(String::new()).lines().filter_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        return Some((fields.get(2)?.parse::<u32>().ok()?, String::from(fields[0])));
+    }).collect();
+}
+
+fn get_cmdline(path: &str) -> Option<String> {
+    let raw = fs::read(format!("{}/cmdline", path)).ok()?;
+    let mut args = raw.split(|b| *b == 0).filter(|a| !a.is_empty()).map(|a| String::from_utf8_lossy(a).to_string());
+
+    let argv0 = args.next()?;
+    let name = argv0.rsplit('/').next().unwrap_or(&argv0).to_string();
+    return Some(std::iter::once(name).chain(args).collect::<Vec<String>>().join(" "));
+}
+
+fn get_cgroup(path: &str) -> String {
+    let lines = try_strings_from_path(&format!("{}/cgroup", path), 32).unwrap_or(Vec::new());
+
+    // cgroup v2 is the `0::` line, v1 has one per controller and the first will do
+    let cgroup = match lines.iter().find(|l| l.starts_with("0::")).or(lines.get(0)) {
+        Some(l) => l.rsplit('/').next().unwrap_or(""),
+        None => return String::new(),
+    };
+
+    let name = cgroup.trim_end_matches(".scope").trim_end_matches(".service");
+    for runtime in &["docker-", "libpod-", "cri-containerd-", "crio-"] {
+        if name.starts_with(runtime) {
+            return name.chars().take(runtime.len() + 12).collect();
+        }
+    }
+
+    return String::from(name);
+}
+
+fn get_ps_from_proc(counter: u64, mod_top: u64, mem_used: f64, wants: PsWants) -> Vec<PsInfo> {
     let mut procs = Vec::new();
     let cpu_loads_map  = &mut CPU_LOADS.lock().unwrap();
     let proc_files_map = &mut PROC_PID_FILES.lock().unwrap();
     let should_run_retain = counter % (mod_top * 5) == 0;
 
+    // process CPU use is against one CPU's ticks, cpu0 can be offline
+    let cpu_ticks = match cpu_loads_map.get(&0) {
+        Some(load) => load.total as f64,
+        None => {
+            let online = cpu_loads_map.keys().filter(|i| **i >= 0).count().max(1);
+            cpu_loads_map.get(&-1).map(|load| load.total as f64 / online as f64).unwrap_or(0.0)
+        },
+    };
+
     fn _hack(line_num: &i32, line: &str) -> bool {
         if line_num == &0 {
             let first = line.bytes().nth(6).unwrap();
@@ -226,8 +857,9 @@ fn get_ps_from_proc(counter: u64, mod_top: u64, mem_used: f64) -> Vec<PsInfo> {
         return true;
     }
 
+    // state, nice, threads and start time come along when asked for
     #[inline(always)]
-    fn _do_cpu(path: &str, pid: &str, total_time: f64) -> f32 {
+    fn _do_cpu(path: &str, pid: &str, total_time: f64, details: bool) -> (f32, Option<(char, i64, u64, u64)>) {
         let proc_loads_map = &mut PROC_LOAD_HIST.lock().unwrap();
         let readers_map = &mut PROC_STAT_READERS.lock().unwrap();
         let pid_u32   = pid.parse::<u32>().unwrap();
@@ -236,7 +868,7 @@ fn get_ps_from_proc(counter: u64, mod_top: u64, mem_used: f64) -> Vec<PsInfo> {
             let p = &format!("{}/stat", &path);
             let tmp_reader = BufReader::new(match File::open(p) {
                 Ok(f)  => f,
-                Err(_) => return 0.0,
+                Err(_) => return (0.0, None),
             });
 
             readers_map.insert(pid_u32, tmp_reader);
@@ -247,7 +879,7 @@ fn get_ps_from_proc(counter: u64, mod_top: u64, mem_used: f64) -> Vec<PsInfo> {
             Ok(_)  => (),
             Err(_) => {
                 readers_map.remove(&pid_u32);
-                return 0.0;
+                return (0.0, None);
             },
         };
 
@@ -255,27 +887,72 @@ fn get_ps_from_proc(counter: u64, mod_top: u64, mem_used: f64) -> Vec<PsInfo> {
             Ok(v)  => v,
             Err(_) => {
                 readers_map.remove(&pid_u32);
-                return 0.0;
+                return (0.0, None);
             },
         };
 
-        let stat_vec  = split_spc_to_strs!(stat_line[0]);
+        // comm is in parentheses and can have spaces, the fields are counted from after it
+        let line = &stat_line[0];
+        let stat_vec = match line.rfind(')') {
+            Some(i) => split_spc_to_strs!(line[(i + 1)..]),
+            None => return (0.0, None),
+        };
+
+        if stat_vec.len() < 20 {
+            return (0.0, None);
+        }
 
-        let proc_time: f64 = stat_vec[13].parse::<f64>().unwrap() + stat_vec[14].parse::<f64>().unwrap();
+        let num = |i: usize| stat_vec[i].parse::<f64>().unwrap_or(0.0);
+        let proc_time: f64 = num(11) + num(12);
 
         if !proc_loads_map.contains_key(&pid_u32) {
             proc_loads_map.insert(pid_u32, (0.0, 0.0));
         }
 
         let last = proc_loads_map.get(&pid_u32).unwrap();
-        let util = 100.0 * (proc_time - last.0) / (total_time - last.1);
+        let util = match total_time > last.1 {
+            true  => 100.0 * (proc_time - last.0) / (total_time - last.1),
+            false => 0.0,
+        };
 
         proc_loads_map.insert(pid_u32, (proc_time, total_time));
-        return util as f32;
+
+        let extra = match details {
+            true  => Some((stat_vec[0].chars().next().unwrap_or('?'), num(16) as i64, num(17) as u64, num(19) as u64)),
+            false => None,
+        };
+
+        return (util as f32, extra);
+    }
+
+    // only other users' processes are off limits without root
+    fn _do_io(path: &str, pid: &str) -> Option<(f64, f64)> {
+        let io_hist = &mut PROC_IO_HIST.lock().unwrap();
+        let lines = try_strings_from_path(&format!("{}/io", path), 7).ok()?;
+        let field = |name: &str| lines.iter()
+            .find(|l| l.starts_with(name))
+            .and_then(|l| l[name.len()..].trim().parse::<u64>().ok());
+
+        let (read, write) = (field("read_bytes:")?, field("write_bytes:")?);
+        let now = Instant::now();
+
+        return match io_hist.insert(pid.parse::<u32>().ok()?, (read, write, now)) {
+            Some((last_read, last_write, then)) => {
+                let secs = now.duration_since(then).as_secs_f64();
+                match secs > 0.0 {
+                    true  => Some((read.saturating_sub(last_read) as f64 / secs, write.saturating_sub(last_write) as f64 / secs)),
+                    false => Some((0.0, 0.0)),
+                }
+            },
+            None => Some((0.0, 0.0)),
+        };
     }
 
     let mut pids = HashSet::new();
-    let match_vec = &vec!["Name", "VmRSS"];
+    let match_vec = &match wants.details {
+        true  => vec!["Name", "Uid", "VmRSS", "VmSwap"],
+        false => vec!["Name", "VmRSS"],
+    };
 
     fs::read_dir("/proc").unwrap().for_each(|dir_entry| {
         #[cfg(not(feature = "timings"))]
@@ -332,27 +1009,57 @@ fn get_ps_from_proc(counter: u64, mod_top: u64, mem_used: f64) -> Vec<PsInfo> {
                 },
             };
 
-            if status_lines.len() != 2 { return; }
+            // kernel threads have no VmRSS and are left out
+            let status = |prefix: &str| status_lines.iter().find(|l| l.starts_with(prefix));
+            let kb = |prefix: &str| status(prefix)
+                .and_then(|l| l.split_ascii_whitespace().nth(1))
+                .and_then(|n| n.parse::<f64>().ok());
 
-            let proc_used = status_lines[1][7..(status_lines[1].len() - 3)].trim().parse::<f64>();
+            let (comm, used) = match (status("Name:"), kb("VmRSS:")) {
+                (Some(name), Some(used)) => (String::from(name[6..].trim()), used),
+                _ => return,
+            };
+
+            let (cpu, stat) = _do_cpu(&path, &pid, cpu_ticks, wants.details);
+            let details = stat.map(|(state, nice, threads, start)| {
+                let uid = status("Uid:")
+                    .and_then(|l| l.split_ascii_whitespace().nth(1))
+                    .and_then(|n| n.parse::<u32>().ok());
+
+                return PsDetails {
+                    user: match uid {
+                        Some(uid) => USER_NAMES.get(&uid).cloned().unwrap_or(format!("{}", uid)),
+                        None => String::new(),
+                    },
+                    state:   state,
+                    nice:    nice,
+                    threads: threads,
+                    rss:     (used * 1024.0) as u64,
+                    swap:    (kb("VmSwap:").unwrap_or(0.0) * 1024.0) as u64,
+                    start:   start / (*CLK_TCK).max(1),
+                    cmdline: get_cmdline(&path).unwrap_or(comm.clone()),
+                    cgroup:  get_cgroup(&path),
+                };
+            });
 
-            match proc_used {
-                Ok(used) => {
-                    procs.push(PsInfo {
-                        comm: String::from(&status_lines[0][6..]),
-                        pid: String::from(pid),
-                        cpu: _do_cpu(&path, &pid, cpu_loads_map[&0].total as f64),
-                        mem: (used / mem_used) as f32,
-                    });
+            procs.push(PsInfo {
+                comm: comm,
+                pid: String::from(pid),
+                cpu: cpu,
+                mem: (used / mem_used) as f32,
+                io: match wants.io {
+                    true  => _do_io(&path, &pid),
+                    false => None,
                 },
-                _ => (),
-            };
+                details: details,
+            });
         }
     });
 
     if should_run_retain {
         PROC_STAT_READERS.lock().unwrap().retain(|i, _| { pids.contains(&i.to_string()) });
         proc_files_map.retain(|i, _| { pids.contains(i) });
+        PROC_IO_HIST.lock().unwrap().retain(|i, _| { pids.contains(&i.to_string()) });
     }
 
     return procs;
@@ -386,46 +1093,158 @@ fn get_ps() -> Vec<PsInfo> {
             cpu:  tmp[1].parse::<f32>().unwrap(),
             mem:  tmp[2].parse::<f32>().unwrap(),
             comm: tmp[3].to_string(),
+            io:   None,
+            details: None,
         });
     }
 
     return ps_info_vec;
 }
 
-pub fn get_battery(path: &str) -> (bool, String) {
-    let mut reader_map = BATTERY_CACHE.lock().unwrap();
-    let path_string = String::from(path);
+const POWER_SUPPLY: &str = "/sys/class/power_supply";
 
-    if !reader_map.contains_key(path) {
-        let cap_reader    = BufReader::new(File::open(format!("{}/capacity", path)).unwrap());
-        let status_reader = BufReader::new(File::open(format!("{}/status", path)).unwrap());
-        reader_map.insert(path_string, (cap_reader, status_reader));
+#[derive(Clone, Copy, PartialEq)]
+pub enum BatteryState {
+    Charging,
+    Discharging,
+    Full,
+    // plugged in but holding the charge, thresholds or a full enough battery
+    NotCharging,
+    Unknown,
+}
+
+// energy in Wh, power in W. Batteries that report charge (µAh) and current are converted with voltage_now
+pub struct Battery {
+    pub capacity: u64,
+    pub state:    BatteryState,
+    pub power:    Option<f64>,
+    pub energy:   Option<f64>,
+    pub energy_full:   Option<f64>,
+    pub energy_design: Option<f64>,
+    pub voltage:  Option<f64>,
+    pub cycles:   Option<u64>,
+}
+
+impl Battery {
+    // to empty when discharging, to full when charging
+    pub fn time_left(&self) -> Option<Duration> {
+        let power = self.power.filter(|p| *p > 0.0)?;
+        let hours = match self.state {
+            BatteryState::Discharging => self.energy? / power,
+            BatteryState::Charging    => (self.energy_full? - self.energy?).max(0.0) / power,
+            _ => return None,
+        };
+
+        return Some(Duration::from_secs((hours * 3600.0) as u64));
     }
 
-    let (cap_reader, status_reader) = reader_map.get_mut(path).unwrap();
-    let capacity = &try_strings_from_reader(cap_reader, 1).unwrap()[0];
+    // % of the design capacity it still holds
+    pub fn health(&self) -> Option<f64> {
+        return match (self.energy_full, self.energy_design) {
+            (Some(full), Some(design)) if design > 0.0 => Some(full / design * 100.0),
+            _ => None,
+        };
+    }
+}
+
+fn read_supply(path: &str, file: &str) -> Option<String> {
+    return fs::read_to_string(format!("{}/{}", path, file)).ok().map(|s| String::from(s.trim()));
+}
+
+pub fn get_battery(path: &str) -> Battery {
+    return parse_battery(|file| read_supply(path, file));
+}
+
+// `read` gives the trimmed contents of a file in the supply's directory.
+// sysfs has everything in micro units
+fn parse_battery<F: Fn(&str) -> Option<String>>(read: F) -> Battery {
+    let micro = |file: &str| -> Option<f64> {
+        return read(file)?.parse::<f64>().ok().map(|n| n / 1000000.0);
+    };
+
+    let voltage = micro("voltage_now");
+    let in_wh = |energy: &str, charge: &str| -> Option<f64> {
+        return micro(energy).or_else(|| Some(micro(charge)? * voltage?));
+    };
+
+    let power = micro("power_now")
+        .or_else(|| Some(micro("current_now")? * voltage?))
+        .map(|p| p.abs());
+
+    return Battery {
+        capacity: read("capacity").and_then(|c| c.parse().ok()).unwrap_or(0),
+        state: match read("status").as_ref().map(|s| s.as_str()) {
+            Some("Charging")     => BatteryState::Charging,
+            Some("Discharging")  => BatteryState::Discharging,
+            Some("Full")         => BatteryState::Full,
+            Some("Not charging") => BatteryState::NotCharging,
+            _ => BatteryState::Unknown,
+        },
+        power:    power,
+        energy:   in_wh("energy_now", "charge_now"),
+        energy_full:   in_wh("energy_full", "charge_full"),
+        energy_design: in_wh("energy_full_design", "charge_full_design"),
+        voltage:  voltage,
+        // 0 when the firmware doesn't count them
+        cycles:   read("cycle_count").and_then(|c| c.parse().ok()).filter(|c| *c > 0),
+    };
+}
 
-    let status   = match try_strings_from_reader(status_reader, 1).unwrap()[0].as_str() {
-        "Discharging" => false,
-        "Full"        => true,
-        "Unknown"     => true,
-        _             => true,
+fn supplies_of(kinds: &[&str]) -> Vec<(String, String)> {
+    let mut supplies: Vec<(String, String)> = match fs::read_dir(POWER_SUPPLY) {
+        Ok(entries) => entries.flatten()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .map(|name| (name.clone(), format!("{}/{}", POWER_SUPPLY, name)))
+            .filter(|(_, path)| read_supply(path, "type").map(|t| kinds.contains(&t.as_str())).unwrap_or(false))
+            // mice, keyboards and headsets have scope Device
+            .filter(|(_, path)| read_supply(path, "scope").as_ref().map(|s| s.as_str()) != Some("Device"))
+            .collect(),
+        Err(_) => Vec::new(),
     };
 
-    return (status, String::from(capacity));
+    supplies.sort();
+    return supplies;
 }
 
-fn get_cpu_voltage_rpi() -> String {
+// (name, path) of the system's batteries, BAT0, BAT1...
+pub fn get_battery_paths() -> Vec<(String, String)> {
+    return supplies_of(&["Battery"]);
+}
+
+// the path under /sys/class/power_supply when only the name is given
+pub fn battery_path(item: &Yaml) -> String {
+    return match (item["path"].as_str(), item["name"].as_str()) {
+        (Some(path), _) => String::from(path),
+        (None, Some(name)) => format!("{}/{}", POWER_SUPPLY, name),
+        _ => String::new(),
+    };
+}
+
+// None on desktops and anything else without an AC adapter or USB-C charger
+pub fn get_ac_online() -> Option<bool> {
+    let adapters = supplies_of(&["Mains", "USB"]);
+    if adapters.is_empty() {
+        return None;
+    }
+
+    return Some(adapters.iter().any(|(_, path)| read_supply(path, "online").as_ref().map(|s| s.as_str()) == Some("1")));
+}
+
+fn get_cpu_voltage_rpi() -> Deet {
     let output = match Command::new("vcgencmd").arg("measure_volts").arg("core").output() {
         Ok(o) => o,
         Err(e) => panic!("Error running vcgencmd to get volts: {}", e)
     };
 
     let out_str = String::from_utf8_lossy(&output.stdout);
-    return String::from(split_to_strs!(out_str.trim(), '=')[1]);
+    let volts_str = split_to_strs!(out_str.trim(), '=')[1].trim_end_matches('V');
+    return match volts_str.parse::<f64>() {
+        Ok(v)  => Deet::Measure { value: v, unit: "V", precision: 4 },
+        Err(_) => Deet::Text(String::from(volts_str)),
+    };
 }
 
-fn get_cpu_speed_rpi() -> String {
+fn get_cpu_speed_rpi() -> Deet {
     let output = match Command::new("vcgencmd").arg("measure_clock").arg("arm").output() {
         Ok(o) => o,
         Err(e) => panic!("Error running vcgencmd to get clock: {}", e)
@@ -435,49 +1254,71 @@ fn get_cpu_speed_rpi() -> String {
     let mhz_str = split_to_strs!(out_str.trim(), '=')[1];
     let mhz = mhz_str.parse::<u32>().unwrap() / 1000 / 1000;
 
-    return format!("{} MHz", mhz);
+    return Deet::Measure { value: mhz as f64, unit: " MHz", precision: 0 };
 }
 
 #[cfg(feature = "nvidia")]
-fn get_nvidia_gpu_temp(idx: u32) -> String {
+fn get_nvidia_gpu_temp(idx: u32) -> Deet {
     let nvml = NVML_O.lock().unwrap();
     let device = nvml.device_by_index(idx).unwrap();
     let temperature = device.temperature(TemperatureSensor::Gpu).unwrap();
-    return format!("{}C", temperature);
+    return Deet::Measure { value: temperature as f64, unit: "C", precision: 0 };
 }
 
-pub fn do_func(item: &Yaml, frame_cache: &FrameCache) -> String {
+pub fn do_func(item: &Yaml, frame_cache: &FrameCache) -> Deet {
     let func: &str = item["func"].as_str().unwrap();
-    let val: Option<&str> = item["val"].as_str();
 
-    let ret: String = match func {
+    let ret: Deet = match func {
         "hostname" =>    timings!(func, get_hostname_from_utsname, frame_cache.utsname.nodename as [c_char; 65]),
         "kernel" =>      timings!(func, get_uname, frame_cache.utsname.release as [c_char; 65]),
-        "uptime" =>      timings!(func, get_uptime_string, frame_cache.sysinfo.uptime as c_int),
+        "uptime" =>      timings!(func, get_uptime, frame_cache.sysinfo.uptime as c_long),
         "load" =>        timings!(func, get_load, frame_cache.sysinfo.loads as [c_ulong; 3]),
         "procs_count" => timings!(func, get_procs_count, &frame_cache.proc_stat),
+        "procs_blocked" => timings!(func, get_procs_blocked, &frame_cache.proc_stat),
+        "ctxt" =>        timings!(func, get_activity_deet, frame_cache, "ctxt"),
+        "intr" =>        timings!(func, get_activity_deet, frame_cache, "intr"),
+        "forks" =>       timings!(func, get_activity_deet, frame_cache, "processes"),
+
+        "page_faults" =>  timings!(func, get_vmstat_deet, frame_cache, "pgfault", "/s"),
+        "major_faults" => timings!(func, get_vmstat_deet, frame_cache, "pgmajfault", "/s"),
+        "swap_in" =>      timings!(func, get_vmstat_deet, frame_cache, "pswpin", " pages/s"),
+        "swap_out" =>     timings!(func, get_vmstat_deet, frame_cache, "pswpout", " pages/s"),
+        "pgscan" =>       timings!(func, get_vmstat_deet, frame_cache, "pgscan", " pages/s"),
+        "pgsteal" =>      timings!(func, get_vmstat_deet, frame_cache, "pgsteal", " pages/s"),
+        "oom_kills" =>    match frame_cache.vmstat.get("oom_kill") {
+            Some(c) => Deet::Count(c.total),
+            None => Deet::Unknown,
+        },
 
-        "ram_usage" => format!("{:.2}GB / {:.2}GB",
-                               (frame_cache.mem_total - frame_cache.mem_free),
-                               frame_cache.mem_total),
-        "cpu_usage" => format!("{:.2}%", timings!(func, get_cpu_usage, -1)),
+        "ram_usage"  => Deet::BytesOf(frame_cache.mem("MemTotal").saturating_sub(frame_cache.mem_available()), frame_cache.mem("MemTotal")),
+        "swap_usage" => Deet::BytesOf(frame_cache.mem("SwapTotal").saturating_sub(frame_cache.mem("SwapFree")), frame_cache.mem("SwapTotal")),
+        "cpu_usage" => Deet::Percent(timings!(func, get_cpu_usage, -1)),
+        "cpu_time"  => timings!(func, get_cpu_time, item),
 
-        "cpu_temp_sys" =>    timings!(func, get_cpu_temp_sys, val),
+        "cpu_temp_sys" =>    timings!(func, get_cpu_temp_sys),
         "cpu_speed_rpi" =>   timings!(func, get_cpu_speed_rpi),
         "cpu_voltage_rpi" => timings!(func, get_cpu_voltage_rpi),
+        "pressure" =>        timings!(func, get_pressure_deet, item, frame_cache),
 
         #[cfg(feature = "nvidia")]
         "nvidia_gpu_temp" => timings!("nvidia_temp", get_nvidia_gpu_temp, item["idx"].as_i64().unwrap() as u32),
 
+        "hwmon" => timings!(func, get_hwmon,
+                            item["sensor_name"].as_str().unwrap(),
+                            item["label_name"].as_str().unwrap()),
+
         #[cfg(feature = "sensors")]
         "sensor_info" => timings!("sensors", get_sensor_info,
                                   item["sensor_name"].as_str().unwrap(),
-                                  item["label_name"].as_str().unwrap(),
-                                  item["val"].as_str().unwrap(),
-                                  item["whole"].as_bool().unwrap()),
+                                  item["label_name"].as_str().unwrap()),
+
+        #[cfg(not(feature = "sensors"))]
+        "sensor_info" => timings!("sensors", get_hwmon,
+                                  item["sensor_name"].as_str().unwrap(),
+                                  item["label_name"].as_str().unwrap()),
         _ => {
-            println!("Unknown func: {}", func);
-            return String::from("unimpl");
+            eprintln!("Unknown func: {}", func);
+            return Deet::Text(String::from("unimpl"));
         },
     };
 
@@ -505,23 +1346,13 @@ pub fn get_fs(keys: Vec<&str>) -> HashMap<String, FileSystemUsage> {
                 let mut statvfs: libc::statvfs = unsafe { mem::zeroed() };
                 unsafe { libc::statvfs(test.as_ptr(), &mut statvfs) };
 
-		let free  = ((statvfs.f_bsize as f64 / 1024.0) * (statvfs.f_bfree as f64 / 1024.0)) / 1024.0;
-                let mut total = ((statvfs.f_frsize as f64 / 1024.0) * (statvfs.f_blocks as f64 / 1024.0)) / 1024.0;
-                let mut used  = total - free;
-                let mut size_char = 'G';
-
-		if total < 1.0 {
-                    used = used * 100.0;
-                    total = total * 100.0;
-                    size_char = 'M';
-                }
+                // block counts are in f_frsize units, f_bsize can be bigger
+                let free  = statvfs.f_frsize as u64 * statvfs.f_bfree as u64;
+                let total = statvfs.f_frsize as u64 * statvfs.f_blocks as u64;
 
                 map.insert(String::from(**path), FileSystemUsage {
-                    used: used,
+                    used: total.saturating_sub(free),
                     total: total,
-                    used_str: format!("{:.2}{}", used, size_char),
-                    total_str: format!("{:.2}{}", total, size_char),
-                    use_pct: format!("{:.0}%", (used / total) * 100.0),
                 });
 
                 found_count += 1;
@@ -536,9 +1367,9 @@ pub fn get_fs(keys: Vec<&str>) -> HashMap<String, FileSystemUsage> {
 
 #[allow(dead_code)]
 pub fn get_fs_from_df(keys: Vec<&str>) -> HashMap<String, FileSystemUsage> {
-    let output = match Command::new("df").arg("-h").output() {
+    let output = match Command::new("df").arg("-B1").output() {
         Ok(o) => o,
-        Err(e) => panic!("Error running df -h!: {}", e)
+        Err(e) => panic!("Error running df -B1!: {}", e)
     };
 
     let out_str = String::from_utf8_lossy(&output.stdout);
@@ -550,11 +1381,8 @@ pub fn get_fs_from_df(keys: Vec<&str>) -> HashMap<String, FileSystemUsage> {
         for key in keys.iter() {
             if key == &row[5] {
                 map.insert(String::from(*key), FileSystemUsage {
-                    used: row[2][0..row[2].len()-1].parse().unwrap(),
-                    total: row[1][0..row[1].len()-1].parse().unwrap(),
-                    used_str: String::from(&row[2]),
-                    total_str: String::from(&row[1]),
-                    use_pct: String::from(&row[4]),
+                    used: row[2].parse().unwrap(),
+                    total: row[1].parse().unwrap(),
                 });
             }
         }
@@ -563,23 +1391,42 @@ pub fn get_fs_from_df(keys: Vec<&str>) -> HashMap<String, FileSystemUsage> {
     return map;
 }
 
-fn _do_top(counter: u64, mod_top: u64, do_top_bool: bool, mem_total: f64) -> Vec<PsInfo> {
+fn _do_top(counter: u64, mod_top: u64, do_top_bool: bool, wants: PsWants, mem_total: u64) -> Vec<PsInfo> {
+    // VmRSS is in kB, this turns it straight into a percent of mem_total
+    let mem_total_kb = mem_total as f64 / 1024.0;
     return match do_top_bool {
-        true => get_ps_from_proc(counter, mod_top, mem_total * 10000.0),
+        true => get_ps_from_proc(counter, mod_top, mem_total_kb / 100.0, wants),
         false => Vec::new()
     };
 }
 
-pub fn get_frame_cache(counter: u64, mod_top: u64, do_top_bool: bool) -> FrameCache {
+pub fn get_frame_cache(counter: u64, mod_top: u64, do_top_bool: bool, wants: PsWants, cache_wants: CacheWants) -> FrameCache {
     let proc_stat = timings!("proc_stat", get_proc_stat);
     // Always warm this cache up!
     timings!("all_cpu", do_all_cpu_usage, &proc_stat);
+    let activity = timings!("activity", get_activity, &proc_stat);
+    let vmstat = match cache_wants.vmstat {
+        true  => timings!("vmstat", get_vmstat),
+        false => HashMap::new(),
+    };
 
-    let mem = timings!("ram_usage", get_ram_usage);
-    let ps_info = timings!("ps_info", _do_top, counter, mod_top, do_top_bool, mem.1);
+    let meminfo = match cache_wants.meminfo {
+        true  => timings!("meminfo", get_meminfo),
+        false => HashMap::new(),
+    };
     let sysinfo = timings!("sysinfo", get_sysinfo);
+    let mem_total = sysinfo.totalram as u64 * sysinfo.mem_unit as u64;
+    let ps_info = timings!("ps_info", _do_top, counter, mod_top, do_top_bool, wants, mem_total);
     let utsname = timings!("utsname", get_utsname);
     let net_dev = timings!("net_dev", get_net_dev);
+    let disk_stats = match cache_wants.disk_stats {
+        true  => timings!("disk_stats", get_disk_stats),
+        false => HashMap::new(),
+    };
+    let pressure = match cache_wants.pressure {
+        true  => timings!("pressure", get_pressure),
+        false => HashMap::new(),
+    };
 
     #[cfg(feature = "timings")]
     println!("Size of PROC_PID_FILES: {}", PROC_PID_FILES.lock().unwrap().len());
@@ -591,65 +1438,253 @@ pub fn get_frame_cache(counter: u64, mod_top: u64, do_top_bool: bool) -> FrameCa
         utsname:   utsname,
         ps_info:   ps_info,
         proc_stat: proc_stat,
-        mem_free:  mem.0,
-        mem_total: mem.1,
+        meminfo:   meminfo,
+        activity:  activity,
+        vmstat:    vmstat,
         net_dev: net_dev,
+        disk_stats: disk_stats,
+        pressure: pressure,
     };
 }
 
-fn get_net_dev() -> HashMap<String, (u64, u64)> {
+// the rates are worked out once a frame, however many modules read them
+fn get_net_dev() -> HashMap<String, (Counter, Counter)> {
     let lines = try_strings_from_path("/proc/net/dev", 1024).unwrap();
-    let mut map: HashMap<String, (u64, u64)> = HashMap::new();
+    let totals = &mut COUNTER_TOTALS.lock().unwrap();
+    let mut map: HashMap<String, (Counter, Counter)> = HashMap::new();
 
     lines.iter().skip(2).for_each(|line| {
         let tokens = split_spc_to_strs!(line);
-        map.insert(String::from(&tokens[0][0..(tokens[0].len() - 1)]),
-                   (tokens[9].parse::<u64>().unwrap(), tokens[1].parse::<u64>().unwrap()));
+        let interface = &tokens[0][0..(tokens[0].len() - 1)];
+        let up = counter(totals, &format!("net {} up", interface), tokens[9].parse::<u64>().unwrap());
+        let down = counter(totals, &format!("net {} down", interface), tokens[1].parse::<u64>().unwrap());
+        map.insert(String::from(interface), (up, down));
     });
 
     return map;
 }
 
+fn get_disk_stats() -> HashMap<String, DiskStat> {
+    let reader = &mut DISKSTATS_READER.lock().unwrap();
+    let lines = match reader.as_mut() {
+        Some(r) => try_strings_from_reader(r, 1024).unwrap_or(Vec::new()),
+        None => Vec::new(),
+    };
+
+    return lines.iter().filter_map(|line| parse_disk_stat(line)).collect();
+}
+
+// `   8       0 sda 1234 ...`, the counts are in 512 byte sectors whatever the device's own size
+fn parse_disk_stat(line: &str) -> Option<(String, DiskStat)> {
+    let tokens = split_spc_to_strs!(line);
+    if tokens.len() < 13 {
+        return None;
+    }
+
+    let num = |i: usize| tokens[i].parse::<u64>().unwrap_or(0);
+    return Some((String::from(tokens[2]), DiskStat {
+        reads: num(3),
+        writes: num(7),
+        read_bytes: num(5) * 512,
+        write_bytes: num(9) * 512,
+        io_ticks: num(12),
+    }));
+}
+
+fn get_pressure() -> HashMap<String, Pressure> {
+    let mut map: HashMap<String, Pressure> = HashMap::new();
+    let totals = &mut PSI_TOTALS.lock().unwrap();
+    let readers = &mut PRESSURE_READERS.lock().unwrap();
+
+    for (resource, reader) in readers.iter_mut() {
+        let lines = match try_strings_from_reader(reader, 2) {
+            Ok(l)  => l,
+            Err(_) => continue,
+        };
+
+        let mut with_rate = |line: &String| -> Option<PressureLine> {
+            let (kind, mut pressure_line, total) = parse_pressure_line(line)?;
+            let now = Instant::now();
+            pressure_line.rate = match totals.insert(format!("{} {}", resource, kind), (total, now)) {
+                Some((last, then)) => {
+                    let elapsed = now.duration_since(then).as_micros() as f64;
+                    match elapsed > 0.0 {
+                        true  => (total.saturating_sub(last) as f64 / elapsed * 100.0).min(100.0),
+                        false => 0.0,
+                    }
+                },
+                None => 0.0,
+            };
+
+            return Some(pressure_line);
+        };
+
+        let some = match lines.iter().find(|l| l.starts_with("some")).and_then(|l| with_rate(l)) {
+            Some(s) => s,
+            None => continue,
+        };
+        let full = lines.iter().find(|l| l.starts_with("full")).and_then(|l| with_rate(l));
+
+        map.insert(String::from(*resource), Pressure { some: some, full: full });
+    }
+
+    return map;
+}
+
+// `some avg10=0.00 avg60=0.00 avg300=0.00 total=0`, returns some or full, the line and total= (in microseconds).
+// The rate needs the last frame's total, it's left at 0
+fn parse_pressure_line(line: &str) -> Option<(&str, PressureLine, u64)> {
+    let tokens = split_spc_to_strs!(line);
+    let field = |name: &str| tokens.iter()
+        .find(|t| t.starts_with(name))
+        .and_then(|t| t[name.len()..].parse::<f64>().ok());
+
+    return Some((tokens.get(0)?, PressureLine {
+        avg10: field("avg10=")?,
+        avg60: field("avg60=")?,
+        avg300: field("avg300=")?,
+        rate: 0.0,
+    }, field("total=")? as u64));
+}
+
+fn get_pressure_deet(item: &Yaml, frame_cache: &FrameCache) -> Deet {
+    let pressure = match frame_cache.pressure.get(item["resource"].as_str().unwrap_or("")) {
+        Some(p) => p,
+        None => return Deet::Text(String::from("n/a")),
+    };
+
+    let line = match item["stall"].as_str() {
+        Some("full") => pressure.full,
+        _ => Some(pressure.some),
+    };
+
+    return match line {
+        Some(l) => Deet::Percent(l.get(&avg_key(item))),
+        None => Deet::Text(String::from("n/a")),
+    };
+}
+
+// `avg: 60` and `avg: "60"` both work
+pub fn avg_key(item: &Yaml) -> String {
+    return match (item["avg"].as_i64(), item["avg"].as_str()) {
+        (Some(n), _) => format!("{}", n),
+        (_, Some(s)) => String::from(s),
+        _ => String::from("10"),
+    };
+}
+
+// The /proc/diskstats name of the device mounted at mount_point
+pub fn get_mount_device(mount_point: &str) -> Option<String> {
+    let lines = try_strings_from_path("/proc/mounts", 1024).ok()?;
+    let line = lines.iter().find(|line| split_spc_to_strs!(line).get(1) == Some(&mount_point))?;
+    let source = split_spc_to_strs!(line)[0];
+
+    // /dev/mapper/* and /dev/disk/by-* are links to the real node
+    let path = fs::canonicalize(source).ok()?;
+    return Some(String::from(path.file_name()?.to_str()?));
+}
+
 fn do_all_cpu_usage(proc_stat: &Vec<String>) {
     let loads_map = &mut CPU_LOADS.lock().unwrap();
+    let mut seen: HashSet<i32> = HashSet::new();
+
+    // offline CPUs have no line, so go by the number on it rather than the position
+    for line in proc_stat.iter().filter(|l| l.starts_with("cpu")) {
+        let tokens = split_spc_to_strs!(line);
+        let cpu_num = match tokens[0] {
+            "cpu" => -1,
+            t => match t[3..].parse::<i32>() {
+                Ok(n)  => n,
+                Err(_) => continue,
+            },
+        };
+        seen.insert(cpu_num);
 
-    for cpu_num in -1..*CPU_COUNT {
         if !loads_map.contains_key(&cpu_num) {
             loads_map.insert(cpu_num, CpuLoad {
                 idle:  0,
                 total: 0,
                 percent: 0.0,
+                times:  [0; 9],
+                shares: [0.0; 9],
             });
         }
 
         let last_load = &loads_map[&cpu_num];
 
-        let proc_stat_line_items: Vec<u64> = proc_stat[(cpu_num + 1) as usize]
-            .split_ascii_whitespace()
+        let proc_stat_line_items: Vec<u64> = tokens.iter()
             .filter_map(|s| s.parse::<u64>().ok())
             .collect();
 
-        let idle:  u64 = proc_stat_line_items[3];
-        let total: u64 = proc_stat_line_items.iter().fold(0, |a, b| a + b);
+        let mut times = [0; 9];
+        times.iter_mut().zip(proc_stat_line_items.iter()).for_each(|(t, item)| *t = *item);
+
+        // guest and guest_nice are already in user and nice
+        let idle:  u64 = times[3];
+        let total: u64 = times[..8].iter().sum();
 
-        let totals = total - last_load.total;
-        let idles  = idle - last_load.idle;
+        let totals = total.saturating_sub(last_load.total);
+        let idles  = idle.saturating_sub(last_load.idle);
 
         let mut percent = ((totals as f64 - (idles as f64)) / totals as f64) * 100.0;
         if percent.is_nan() { percent = 0.0 }
 
+        let mut shares = [0.0; 9];
+        if totals > 0 {
+            for i in 0..shares.len() {
+                shares[i] = times[i].saturating_sub(last_load.times[i]) as f64 / totals as f64 * 100.0;
+            }
+        }
+
         loads_map.insert(cpu_num, CpuLoad {
             idle: idle,
             total: total,
             percent: percent,
+            times:  times,
+            shares: shares,
         });
     }
+
+    // a CPU that comes back online starts over
+    loads_map.retain(|cpu_num, _| seen.contains(cpu_num));
 }
 
 pub fn get_cpu_usage(cpu_num: i32) -> f64 {
     let loads_map = CPU_LOADS.lock().unwrap();
-    let last_load = &loads_map[&cpu_num];
-    return last_load.percent;
+    return match loads_map.get(&cpu_num) {
+        Some(l) => l.percent,
+        None => 0.0,
+    };
+}
+
+// % of the last frame spent in each of CPU_TIMES, -1 for all CPUs together
+pub fn get_cpu_times(cpu_num: i32) -> [f64; 9] {
+    let loads_map = CPU_LOADS.lock().unwrap();
+    return match loads_map.get(&cpu_num) {
+        Some(l) => l.shares,
+        None => [0.0; 9],
+    };
+}
+
+fn get_cpu_time(item: &Yaml) -> Deet {
+    let field = item["field"].as_str().unwrap_or("user");
+    let times = get_cpu_times(item["idx"].as_i64().unwrap_or(-1) as i32);
+
+    return match CPU_TIMES.iter().position(|f| *f == field) {
+        Some(i) => Deet::Percent(times[i]),
+        None => Deet::Text(String::from("unknown field")),
+    };
+}
+
+// The CPUs that were online in the last frame, or right now before the first one
+pub fn get_cpu_ids() -> Vec<i32> {
+    let mut ids: Vec<i32> = CPU_LOADS.lock().unwrap().keys().filter(|i| **i >= 0).cloned().collect();
+    if ids.is_empty() {
+        ids = fs::read_to_string("/sys/devices/system/cpu/online").map(|s| parse_cpu_list(&s)).unwrap_or(Vec::new());
+    }
+
+    ids.sort();
+    return ids;
 }
 
 #[inline(always)]
@@ -658,3 +1693,126 @@ fn str_from_bytes(mut buffer: Vec<u8>) -> String {
     buffer.resize(end, 0);
     return String::from_utf8(buffer).unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disk_stat_line() {
+        let (name, stat) = parse_disk_stat("   8       0 sda 1000 20 8000 300 500 10 4000 600 0 700 900").unwrap();
+        assert_eq!(name, "sda");
+        assert_eq!((stat.reads, stat.writes), (1000, 500));
+        assert_eq!((stat.read_bytes, stat.write_bytes), (8000 * 512, 4000 * 512));
+        assert_eq!(stat.io_ticks, 700);
+    }
+
+    #[test]
+    fn short_disk_stat_line() {
+        assert!(parse_disk_stat("   8       0 sda 1000 20").is_none());
+        assert!(parse_disk_stat("").is_none());
+    }
+
+    #[test]
+    fn meminfo_lines() {
+        assert_eq!(parse_meminfo("MemTotal:       16318480 kB"), Some((String::from("MemTotal"), 16318480 * 1024)));
+        // HugePages_* are counts, not sizes
+        assert_eq!(parse_meminfo("HugePages_Total:       4"), Some((String::from("HugePages_Total"), 4)));
+        assert_eq!(parse_meminfo(""), None);
+    }
+
+    #[test]
+    fn pressure_line() {
+        let (kind, line, total) = parse_pressure_line("some avg10=1.50 avg60=0.75 avg300=0.25 total=123456").unwrap();
+        assert_eq!(kind, "some");
+        assert_eq!((line.avg10, line.avg60, line.avg300), (1.5, 0.75, 0.25));
+        assert_eq!(line.rate, 0.0);
+        assert_eq!(total, 123456);
+    }
+
+    #[test]
+    fn pressure_line_missing_fields() {
+        assert!(parse_pressure_line("full avg10=0.00 avg60=0.00 avg300=0.00").is_none());
+        assert!(parse_pressure_line("").is_none());
+    }
+
+    #[test]
+    fn vmstat_keeps_the_shown_fields_and_sums_reclaim() {
+        let lines: Vec<String> = [
+            "nr_free_pages 12345",
+            "pgfault 100",
+            "pgmajfault 5",
+            "pgscan_kswapd 10",
+            "pgscan_direct 20",
+            "pgscan_direct_throttle 99",
+            "pgscan_anon 30",
+            "pgsteal_kswapd_normal 7",
+            "pgsteal_direct_dma32 3",
+            "oom_kill 1",
+        ].iter().map(|l| String::from(*l)).collect();
+        let raw = parse_vmstat(&lines);
+
+        assert_eq!(raw.get("pgfault"), Some(&100));
+        assert_eq!(raw.get("pgmajfault"), Some(&5));
+        // per zone on older kernels, _throttle and the anon/file split count pages again
+        assert_eq!(raw.get("pgscan"), Some(&30));
+        assert_eq!(raw.get("pgsteal"), Some(&10));
+        assert_eq!(raw.get("oom_kill"), Some(&1));
+        assert_eq!(raw.get("pswpin"), None);
+        assert_eq!(raw.get("nr_free_pages"), None);
+    }
+
+    fn supply(files: &[(&str, &str)]) -> Battery {
+        let files: HashMap<String, String> = files.iter().map(|(f, v)| (String::from(*f), String::from(*v))).collect();
+        return parse_battery(|file| files.get(file).cloned());
+    }
+
+    #[test]
+    fn battery_reporting_energy() {
+        let bat = supply(&[
+            ("status", "Charging"),
+            ("capacity", "50"),
+            ("energy_now", "30000000"),
+            ("energy_full", "60000000"),
+            ("power_now", "15000000"),
+            ("cycle_count", "12"),
+        ]);
+
+        assert!(bat.state == BatteryState::Charging);
+        assert_eq!(bat.capacity, 50);
+        assert_eq!((bat.energy, bat.energy_full, bat.power), (Some(30.0), Some(60.0), Some(15.0)));
+        assert_eq!(bat.time_left(), Some(Duration::from_secs(2 * 3600)));
+        assert_eq!(bat.cycles, Some(12));
+        // no design capacity, no health
+        assert_eq!(bat.health(), None);
+    }
+
+    #[test]
+    fn battery_reporting_charge_is_converted_with_the_voltage() {
+        let bat = supply(&[
+            ("status", "Discharging"),
+            ("voltage_now", "12000000"),
+            ("charge_now", "2000000"),
+            ("charge_full", "4000000"),
+            ("charge_full_design", "5000000"),
+            ("current_now", "-1000000"),
+            ("cycle_count", "0"),
+        ]);
+
+        assert_eq!((bat.energy, bat.energy_full, bat.energy_design), (Some(24.0), Some(48.0), Some(60.0)));
+        // some firmware reports the current negative while discharging
+        assert_eq!(bat.power, Some(12.0));
+        assert_eq!(bat.time_left(), Some(Duration::from_secs(2 * 3600)));
+        assert!((bat.health().unwrap() - 80.0).abs() < 1e-9);
+        assert_eq!(bat.cycles, None);
+    }
+
+    #[test]
+    fn battery_without_voltage_or_status() {
+        let bat = supply(&[("charge_now", "2000000"), ("current_now", "1000000")]);
+
+        assert!(bat.state == BatteryState::Unknown);
+        assert_eq!((bat.capacity, bat.energy, bat.power), (0, None, None));
+        assert_eq!(bat.time_left(), None);
+    }
+}
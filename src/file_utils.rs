@@ -3,13 +3,6 @@ use std::fs::File;
 use std::io::{BufRead, BufReader, Seek, SeekFrom};
 use std::io::prelude::*;
 
-pub fn get_strings_from_path(path: &str, line_end: usize) -> Vec<String> {
-    match try_strings_from_path(path, line_end) {
-        Ok(v)  => v,
-        Err(e) => panic!("Unable to open / read {}: {}", &path, e),
-    }
-}
-
 pub fn get_match_strings_from_path(path: &str, filters: &Vec<&str>) -> Vec<String> {
     match try_match_strings_from_path(path, &filters) {
         Ok(v)  => v,
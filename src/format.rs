@@ -0,0 +1,238 @@
+use std::time::Duration;
+
+use yaml_rust::Yaml;
+
+use crate::deets::Deet;
+
+const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+const MIB: f64 = 1024.0 * 1024.0;
+
+// Replaces `{name}` for each of the given fields, `{}` is the first field
+pub fn apply(template: &str, fields: &[(&str, String)]) -> String {
+    let mut ret = String::from(template);
+
+    for (i, (name, val)) in fields.iter().enumerate() {
+        if i == 0 {
+            ret = ret.replace("{}", val);
+        }
+
+        ret = ret.replace(&format!("{{{}}}", name), val);
+    }
+
+    return ret;
+}
+
+pub fn json_string(s: &str) -> String {
+    let mut ret = String::from("\"");
+
+    for c in s.chars() {
+        match c {
+            '"'  => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            '\t' => ret.push_str("\\t"),
+            c if (c as u32) < 0x20 => ret.push_str(&format!("\\u{:04x}", c as u32)),
+            c => ret.push(c),
+        }
+    }
+
+    ret.push('"');
+    return ret;
+}
+
+// `precision:` wins, the older `whole: true` means no decimals
+pub fn precision(item: &Yaml) -> Option<usize> {
+    return match item["precision"].as_i64() {
+        Some(p) => Some(p as usize),
+        None => match item["whole"].as_bool() {
+            Some(true) => Some(0),
+            _ => None,
+        },
+    };
+}
+
+pub fn gib(bytes: u64) -> String {
+    return format!("{:.2}", bytes as f64 / GIB);
+}
+
+// GB for anything bigger than a GB, MB otherwise
+pub fn size(bytes: u64) -> String {
+    let b = bytes as f64;
+    return match b < GIB {
+        true  => format!("{:.2}M", b / MIB),
+        false => format!("{:.2}G", b / GIB),
+    };
+}
+
+#[derive(Clone, Copy)]
+pub struct Units {
+    bits:   bool,
+    prefix: Prefix,
+}
+
+#[derive(Clone, Copy)]
+enum Prefix {
+    // powers of 1024 called KB, MB, GB like randy always did
+    Plain,
+    Si,
+    Iec,
+}
+
+// `units: bytes|bits` and `prefix: si|iec` of the given module
+pub fn units(item: &Yaml) -> Units {
+    return Units {
+        bits: item["units"].as_str() == Some("bits"),
+        prefix: match item["prefix"].as_str() {
+            Some("si")  => Prefix::Si,
+            Some("iec") => Prefix::Iec,
+            _ => Prefix::Plain,
+        },
+    };
+}
+
+pub fn rate(bytes: f64, units: &Units) -> String {
+    let (base, prefixes) = match units.prefix {
+        Prefix::Plain => (1024.0, ["K", "M", "G", "T"]),
+        Prefix::Si    => (1000.0, ["k", "M", "G", "T"]),
+        Prefix::Iec   => (1024.0, ["Ki", "Mi", "Gi", "Ti"]),
+    };
+
+    let (mut val, unit) = match units.bits {
+        true  => (bytes * 8.0 / base, "b"),
+        false => (bytes / base, "B"),
+    };
+
+    let mut i = 0;
+    while val > 990.0 && i < prefixes.len() - 1 {
+        val = val / base;
+        i += 1;
+    }
+
+    return format!("{:.2} {}{}", val, prefixes[i], unit);
+}
+
+fn duration_fields(duration: &Duration) -> Vec<(&'static str, String)> {
+    let uptime = duration.as_secs();
+    let d = uptime / 60 / 60 / 24;
+    let h = (uptime / 60 / 60) - (d * 24);
+    let m = (uptime / 60) - (h * 60) - ((d * 24) * 60);
+    let s = (uptime) - ((d * 24) * 60 * 60) - (h * 60 * 60) - (m * 60);
+
+    return vec![
+        ("default", format!("{}d {}h {:02}m {:02}s", d, h, m, s)),
+        ("d", format!("{}", d)),
+        ("h", format!("{}", h)),
+        ("m", format!("{:02}", m)),
+        ("s", format!("{:02}", s)),
+        ("secs", format!("{}", uptime)),
+    ];
+}
+
+// The template comes from the item's `val:` key, the fields each kind of
+// reading offers are listed in the README
+pub fn deet(deet: &Deet, item: &Yaml) -> String {
+    let precision = precision(item);
+
+    let (default, fields): (String, Vec<(&str, String)>) = match deet {
+        Deet::Text(s)  => (String::from("{}"), vec![("text", s.clone())]),
+        Deet::Count(c) => (String::from("{}"), vec![("count", format!("{}", c))]),
+        Deet::Unknown  => (String::from("{}"), vec![("text", String::from("unknown"))]),
+
+        Deet::Percent(p) => (String::from("{}%"), vec![
+            ("pct", format!("{:.*}", precision.unwrap_or(2), p)),
+        ]),
+
+        Deet::Duration(d) => (String::from("{}"), duration_fields(d)),
+
+        Deet::Loads(l) => {
+            let p = precision.unwrap_or(2);
+            (String::from("{}"), vec![
+                ("default", format!("{:.*} {:.*} {:.*}", p, l[0], p, l[1], p, l[2])),
+                ("1",  format!("{:.*}", p, l[0])),
+                ("5",  format!("{:.*}", p, l[1])),
+                ("15", format!("{:.*}", p, l[2])),
+            ])
+        },
+
+        Deet::BytesOf(used, total) => (String::from("{used}GB / {total}GB"), vec![
+            ("used",  gib(*used)),
+            ("total", gib(*total)),
+            ("free",  gib(total - used)),
+            ("pct",   format!("{:.0}", deet.fraction().unwrap() * 100.0)),
+        ]),
+
+        Deet::Measure { value, unit, precision: p } => (format!("{{}}{}", unit), vec![
+            ("value", format!("{:.*}", precision.unwrap_or(*p), value)),
+        ]),
+    };
+
+    return apply(item["val"].as_str().unwrap_or(&default), &fields);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use yaml_rust::YamlLoader;
+
+    fn item(yaml: &str) -> Yaml {
+        return YamlLoader::load_from_str(yaml).unwrap()[0].clone();
+    }
+
+    #[test]
+    fn apply_fills_named_fields_and_the_first_for_empty_braces() {
+        let fields = [("used", String::from("1")), ("total", String::from("2"))];
+        assert_eq!(apply("{} of {total}", &fields), "1 of 2");
+        assert_eq!(apply("{used}/{total} {unknown}", &fields), "1/2 {unknown}");
+    }
+
+    #[test]
+    fn json_string_escapes() {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(json_string("a\"b\\c\nd\te\u{1}"), "\"a\\\"b\\\\c\\nd\\te\\u0001\"");
+    }
+
+    #[test]
+    fn precision_prefers_precision_over_whole() {
+        assert_eq!(precision(&item("precision: 3\nwhole: true")), Some(3));
+        assert_eq!(precision(&item("whole: true")), Some(0));
+        assert_eq!(precision(&item("whole: false")), None);
+    }
+
+    #[test]
+    fn size_switches_to_gigabytes() {
+        assert_eq!(size(512 * 1024 * 1024), "512.00M");
+        assert_eq!(size(3 * 1024 * 1024 * 1024), "3.00G");
+        assert_eq!(gib(1024 * 1024 * 1024 / 2), "0.50");
+    }
+
+    #[test]
+    fn rate_units_and_prefixes() {
+        assert_eq!(rate(2048.0, &units(&Yaml::Null)), "2.00 KB");
+        assert_eq!(rate(1000.0, &units(&item("prefix: si"))), "1.00 kB");
+        assert_eq!(rate(1000.0, &units(&item("units: bits\nprefix: si"))), "8.00 kb");
+        assert_eq!(rate(2048.0 * 1024.0, &units(&item("prefix: iec"))), "2.00 MiB");
+        // past 990 of one prefix it moves on to the next
+        assert_eq!(rate(1000.0 * 1024.0 * 1024.0, &units(&Yaml::Null)), "0.98 GB");
+    }
+
+    #[test]
+    fn deet_defaults() {
+        assert_eq!(deet(&Deet::Percent(42.0), &Yaml::Null), "42.00%");
+        assert_eq!(deet(&Deet::Count(7), &Yaml::Null), "7");
+        assert_eq!(deet(&Deet::Unknown, &Yaml::Null), "unknown");
+        assert_eq!(deet(&Deet::Loads([0.5, 1.0, 1.5]), &Yaml::Null), "0.50 1.00 1.50");
+        assert_eq!(deet(&Deet::Duration(Duration::from_secs(90061)), &Yaml::Null), "1d 1h 01m 01s");
+        assert_eq!(deet(&Deet::BytesOf(1024 * 1024 * 1024, 4 * 1024 * 1024 * 1024), &Yaml::Null), "1.00GB / 4.00GB");
+        assert_eq!(deet(&Deet::Measure { value: 55.26, unit: "°C", precision: 1 }, &Yaml::Null), "55.3°C");
+    }
+
+    #[test]
+    fn deet_templates_and_precision() {
+        assert_eq!(deet(&Deet::Percent(42.4), &item("precision: 0")), "42%");
+        assert_eq!(deet(&Deet::BytesOf(1024 * 1024 * 1024, 4 * 1024 * 1024 * 1024), &item("val: \"{pct}% {free}GB free\"")), "25% 3.00GB free");
+        assert_eq!(deet(&Deet::Duration(Duration::from_secs(90061)), &item("val: \"{d} days {h}:{m}\"")), "1 days 1:01");
+        assert_eq!(deet(&Deet::Loads([0.5, 1.0, 1.5]), &item("val: \"{5}\"\nwhole: true")), "1");
+    }
+}
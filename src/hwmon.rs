@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy)]
+pub enum Kind {
+    Temp,
+    Fan,
+    Volts,
+    Power,
+    Current,
+}
+
+pub struct Sensor {
+    // the hwmon `name` (`coretemp`, `nvme`, `amdgpu`...) or `thermal` for thermal zones
+    pub chip:  String,
+    pub label: String,
+    pub kind:  Kind,
+    path:      PathBuf,
+}
+
+lazy_static! {
+    // (chip, label) to the file with its value, looking them up walks all of /sys/class/hwmon.
+    // A miss remembers when it was looked for, the chip may show up later
+    static ref SENSOR_PATHS: Mutex<HashMap<(String, String), Result<(Kind, PathBuf), Instant>>> = Mutex::new(HashMap::new());
+}
+
+// how long a sensor that wasn't there is left alone before looking again
+const RETRY_MISSING: Duration = Duration::from_secs(30);
+
+impl Kind {
+    // sysfs prefix, the unit it is shown in and what to divide the raw value by
+    fn info(&self) -> (&'static str, &'static str, f64, usize) {
+        return match self {
+            Kind::Temp    => ("temp",  "C",   1000.0,    1),
+            Kind::Fan     => ("fan",   "RPM", 1.0,       0),
+            Kind::Volts   => ("in",    "V",   1000.0,    2),
+            Kind::Power   => ("power", "W",   1000000.0, 1),
+            Kind::Current => ("curr",  "A",   1000.0,    2),
+        };
+    }
+
+    pub fn unit(&self) -> &'static str {
+        return self.info().1;
+    }
+
+    pub fn precision(&self) -> usize {
+        return self.info().3;
+    }
+}
+
+const KINDS: [Kind; 5] = [Kind::Temp, Kind::Fan, Kind::Volts, Kind::Power, Kind::Current];
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    return fs::read_to_string(path).ok().map(|s| String::from(s.trim()));
+}
+
+fn read_value(kind: Kind, path: &Path) -> Option<f64> {
+    let raw = read_trimmed(path)?.parse::<f64>().ok()?;
+    return Some(raw / kind.info().2);
+}
+
+fn hwmon_sensors(dir: &Path, sensors: &mut Vec<Sensor>) {
+    let chip = match read_trimmed(&dir.join("name")) {
+        Some(c) => c,
+        None => return,
+    };
+
+    let mut files: Vec<String> = match fs::read_dir(dir) {
+        Ok(entries) => entries.flatten().map(|e| e.file_name().to_string_lossy().to_string()).collect(),
+        Err(_) => return,
+    };
+    files.sort();
+
+    for file in files.iter() {
+        // temp1_input, fan2_input, power1_average...
+        let (channel, suffix) = match file.find('_') {
+            Some(i) => (&file[..i], &file[(i + 1)..]),
+            None => continue,
+        };
+
+        if suffix != "input" && !(suffix == "average" && channel.starts_with("power")) {
+            continue;
+        }
+
+        let kind = match KINDS.iter().find(|k| {
+            let prefix = k.info().0;
+            return channel.starts_with(prefix) && channel[prefix.len()..].chars().all(|c| c.is_ascii_digit());
+        }) {
+            Some(k) => *k,
+            None => continue,
+        };
+
+        // power has both on some chips, input wins
+        if suffix == "average" && files.contains(&format!("{}_input", channel)) {
+            continue;
+        }
+
+        sensors.push(Sensor {
+            chip:  chip.clone(),
+            label: read_trimmed(&dir.join(format!("{}_label", channel))).unwrap_or(String::from(channel)),
+            kind:  kind,
+            path:  dir.join(file),
+        });
+    }
+}
+
+// Every hwmon channel and thermal zone on this machine
+pub fn list() -> Vec<Sensor> {
+    let mut sensors = Vec::new();
+
+    let mut dirs: Vec<PathBuf> = match fs::read_dir("/sys/class/hwmon") {
+        Ok(entries) => entries.flatten().map(|e| e.path()).collect(),
+        Err(_) => Vec::new(),
+    };
+    dirs.sort();
+    dirs.iter().for_each(|dir| hwmon_sensors(dir, &mut sensors));
+
+    let mut zones: Vec<PathBuf> = match fs::read_dir("/sys/class/thermal") {
+        Ok(entries) => entries.flatten()
+            .filter(|e| e.file_name().to_string_lossy().starts_with("thermal_zone"))
+            .map(|e| e.path())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    zones.sort();
+
+    for zone in zones {
+        sensors.push(Sensor {
+            chip:  String::from("thermal"),
+            label: read_trimmed(&zone.join("type")).unwrap_or(String::from("unknown")),
+            kind:  Kind::Temp,
+            path:  zone.join("temp"),
+        });
+    }
+
+    return sensors;
+}
+
+impl Sensor {
+    pub fn value(&self) -> Option<f64> {
+        return read_value(self.kind, &self.path);
+    }
+}
+
+// lm-sensors names chips like `coretemp-isa-0000`, either that or the plain name matches
+fn chip_matches(chip: &str, wanted: &str) -> bool {
+    return wanted == chip || wanted.starts_with(&format!("{}-", chip));
+}
+
+// The value of the sensor with this chip and label, with its unit and precision
+pub fn read(chip: &str, label: &str) -> Option<(f64, Kind)> {
+    let key = (String::from(chip), String::from(label));
+    let paths = &mut SENSOR_PATHS.lock().unwrap();
+
+    let stale = match paths.get(&key) {
+        Some(Ok(_)) => false,
+        Some(Err(when)) => when.elapsed() >= RETRY_MISSING,
+        None => true,
+    };
+
+    if stale {
+        let found = list().into_iter()
+            .find(|s| chip_matches(&s.chip, chip) && s.label == label)
+            .map(|s| (s.kind, s.path))
+            .ok_or(Instant::now());
+        paths.insert(key.clone(), found);
+    }
+
+    let (kind, path) = paths.get(&key)?.clone().ok()?;
+
+    // unplugged, it may come back as another hwmonN
+    return match read_value(kind, &path) {
+        Some(v) => Some((v, kind)),
+        None => {
+            paths.insert(key, Err(Instant::now()));
+            None
+        },
+    };
+}
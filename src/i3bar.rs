@@ -0,0 +1,63 @@
+use std::io::Write;
+use std::{io, thread, time};
+
+use yaml_rust::Yaml;
+
+use crate::format::json_string;
+use crate::modules::{self, Level, Reading};
+
+// i3bar only understands #rrggbb, so anything else falls back to randy's defaults
+fn hex_color<'a>(settings: &'a Yaml, key: &str, default: &'a str) -> &'a str {
+    return match settings[key].as_str() {
+        Some(c) if c.starts_with('#') => c,
+        _ => default,
+    };
+}
+
+fn block(reading: &Reading, med: &str, high: &str) -> String {
+    let mut ret = format!("{{\"name\":{},\"full_text\":{},\"short_text\":{}",
+                          json_string(&reading.id),
+                          json_string(&format!("{} {}", reading.label, reading.text)),
+                          json_string(&reading.text));
+
+    match reading.level() {
+        Some(Level::Med)  => ret.push_str(&format!(",\"color\":{}", json_string(med))),
+        Some(Level::High) => ret.push_str(&format!(",\"color\":{}", json_string(high))),
+        _ => (),
+    }
+
+    ret.push('}');
+    return ret;
+}
+
+pub fn run(config: &Yaml) {
+    let settings = &config["settings"];
+    let timeout = settings["timeout"].as_i64().unwrap_or(1) as u64;
+    let mod_top = settings["mod_top"].as_i64().unwrap_or(2) as u64;
+    let med  = hex_color(settings, "color_bar_med",  "#ffeeaa");
+    let high = hex_color(settings, "color_bar_high", "#ffaaaa");
+
+    let (_, mut mods) = modules::create_all(&config["ui"], settings);
+
+    let stdout = io::stdout();
+    if writeln!(stdout.lock(), "{{\"version\":1}}\n[").is_err() {
+        return;
+    }
+
+    loop {
+        modules::sample_all(&mut mods, mod_top);
+
+        let blocks = mods.iter()
+            .flat_map(|m| m.readings())
+            .map(|r| block(&r, med, high))
+            .collect::<Vec<String>>();
+
+        let mut handle = stdout.lock();
+        if writeln!(handle, "[{}],", blocks.join(",")).is_err() || handle.flush().is_err() {
+            return;
+        }
+
+        drop(handle);
+        thread::sleep(time::Duration::from_secs(timeout));
+    }
+}
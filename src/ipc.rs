@@ -0,0 +1,168 @@
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::Mutex;
+use std::{fs, thread};
+
+use yaml_rust::Yaml;
+
+use crate::format::json_string;
+use crate::modules::{Module, Reading};
+
+pub enum Command {
+    Refresh,
+    Hide,
+    Show,
+    Reload,
+}
+
+lazy_static! {
+    // None until the socket is up, so nothing is copied around when nobody can ask
+    static ref READINGS: Mutex<Option<Vec<Reading>>> = Mutex::new(None);
+    // where the listener is bound, removed again on the way out
+    static ref SOCKET:   Mutex<Option<String>> = Mutex::new(None);
+}
+
+fn socket_path(settings: &Yaml) -> String {
+    match settings["ipc_socket"].as_str() {
+        Some(p) => return String::from(p),
+        None => (),
+    }
+
+    return match std::env::var("XDG_RUNTIME_DIR") {
+        Ok(dir) if dir != "" => format!("{}/randy.sock", dir),
+        _ => format!("/tmp/randy-{}.sock", unsafe { libc::getuid() }),
+    };
+}
+
+fn reading_json(r: &Reading) -> String {
+    let fraction = match r.fraction {
+        Some(f) => format!("{}", f),
+        None => String::from("null"),
+    };
+
+    return format!("{{\"id\":{},\"label\":{},\"text\":{},\"fraction\":{}}}",
+                   json_string(&r.id), json_string(&r.label), json_string(&r.text), fraction);
+}
+
+fn error(msg: &str) -> String {
+    return format!("{{\"error\":{}}}", json_string(msg));
+}
+
+// Called from modules::sample_all once the frame is sampled
+pub fn publish(modules: &Vec<Box<dyn Module>>) {
+    let mut readings = READINGS.lock().unwrap();
+    if readings.is_none() {
+        return;
+    }
+
+    *readings = Some(modules.iter().flat_map(|m| m.readings()).collect());
+}
+
+fn respond(line: &str, commands: &glib::Sender<Command>) -> String {
+    let tokens = split_spc_to_strs!(line);
+    let send = |cmd: Command| {
+        return match commands.send(cmd) {
+            Ok(_)  => String::from("{\"ok\":true}"),
+            Err(_) => error("randy is shutting down"),
+        };
+    };
+
+    return match tokens.as_slice() {
+        ["items"] => {
+            let readings = READINGS.lock().unwrap();
+            let items = readings.as_ref().unwrap().iter()
+                .map(|r| format!("{{\"id\":{},\"label\":{}}}", json_string(&r.id), json_string(&r.label)))
+                .collect::<Vec<String>>();
+            format!("[{}]", items.join(","))
+        },
+        ["get"] => {
+            let readings = READINGS.lock().unwrap();
+            let items = readings.as_ref().unwrap().iter().map(reading_json).collect::<Vec<String>>();
+            format!("[{}]", items.join(","))
+        },
+        ["get", id] => {
+            let readings = READINGS.lock().unwrap();
+            match readings.as_ref().unwrap().iter().find(|r| &r.id == id) {
+                Some(r) => reading_json(r),
+                None => error(&format!("unknown item: {}", id)),
+            }
+        },
+        ["refresh"] => send(Command::Refresh),
+        ["hide"]    => send(Command::Hide),
+        ["show"]    => send(Command::Show),
+        ["reload"]  => send(Command::Reload),
+        _ => error("unknown command, try: items, get [id], refresh, hide, show, reload"),
+    };
+}
+
+fn serve(stream: UnixStream, commands: glib::Sender<Command>) {
+    let mut writer = match stream.try_clone() {
+        Ok(s)  => s,
+        Err(_) => return,
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(l)  => l,
+            Err(_) => return,
+        };
+
+        if line.trim() == "" {
+            continue;
+        }
+
+        if writeln!(writer, "{}", respond(line.trim(), &commands)).is_err() {
+            return;
+        }
+    }
+}
+
+pub fn start(settings: &Yaml, commands: glib::Sender<Command>) {
+    let path = socket_path(settings);
+
+    // a socket nobody answers on is left over from a randy that went away
+    if UnixStream::connect(&path).is_ok() {
+        eprintln!("Another randy is already listening on {}", path);
+        return;
+    }
+    let _ = fs::remove_file(&path);
+
+    let listener = match UnixListener::bind(&path) {
+        Ok(l)  => l,
+        Err(e) => {
+            eprintln!("Unable to listen on {}: {}", path, e);
+            return;
+        },
+    };
+
+    *READINGS.lock().unwrap() = Some(Vec::new());
+    *SOCKET.lock().unwrap() = Some(path);
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(s) => {
+                    let commands = commands.clone();
+                    thread::spawn(move || serve(s, commands));
+                },
+                Err(_) => (),
+            }
+        }
+    });
+}
+
+// Called once the GTK main loop is done
+pub fn stop() {
+    match SOCKET.lock().unwrap().take() {
+        Some(path) => { let _ = fs::remove_file(&path); },
+        None => (),
+    }
+}
+
+// The listener stays where it is across a reload
+pub fn reloaded(settings: &Yaml) {
+    match SOCKET.lock().unwrap().as_ref() {
+        Some(path) if *path != socket_path(settings) => eprintln!("Still listening on {}, changing ipc_socket takes a restart", path),
+        _ => (),
+    }
+}
@@ -0,0 +1,256 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use yaml_rust::Yaml;
+
+use crate::deets::{self, CacheWants};
+use crate::modules::Frame;
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:9184";
+// scrapes are served one at a time, a client that goes quiet can't hold up the next one for longer
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Exporter {
+    mount_points: Vec<String>,
+    batteries:    Vec<(String, String)>,
+    top:          usize,
+    mod_fs:       u64,
+    mod_bat:      u64,
+    // the slower parts are only refreshed every mod_* frames
+    fs:  String,
+    bat: String,
+    ps:  String,
+}
+
+lazy_static! {
+    static ref EXPORTER: Mutex<Option<Exporter>> = Mutex::new(None);
+    static ref SNAPSHOT: Mutex<String> = Mutex::new(String::new());
+}
+
+fn labels(pairs: &[(&str, &str)]) -> String {
+    let inner = pairs.iter().map(|(k, v)| {
+        let v = v.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+        return format!("{}=\"{}\"", k, v);
+    }).collect::<Vec<String>>().join(",");
+
+    return format!("{{{}}}", inner);
+}
+
+fn family(out: &mut String, name: &str, kind: &str, help: &str, samples: Vec<(String, f64)>) {
+    out.push_str(&format!("# HELP {} {}\n# TYPE {} {}\n", name, help, name, kind));
+    for (l, v) in samples {
+        out.push_str(&format!("{}{} {}\n", name, l, v));
+    }
+}
+
+fn counters(map: &HashMap<String, deets::Counter>, label: &str, names: &[&str]) -> Vec<(String, f64)> {
+    return names.iter()
+        .filter_map(|n| map.get(*n).map(|c| (labels(&[(label, n)]), c.total as f64)))
+        .collect();
+}
+
+fn get_fs(mount_points: &Vec<String>) -> String {
+    let mut out = String::new();
+    let usage = deets::get_fs(mount_points.iter().map(|s| s.as_str()).collect());
+    let mut keys: Vec<&String> = usage.keys().collect();
+    keys.sort();
+
+    family(&mut out, "randy_filesystem_size_bytes", "gauge", "Filesystem size",
+           keys.iter().map(|k| (labels(&[("mount_point", k)]), usage[*k].total as f64)).collect());
+    family(&mut out, "randy_filesystem_used_bytes", "gauge", "Filesystem space used",
+           keys.iter().map(|k| (labels(&[("mount_point", k)]), usage[*k].used as f64)).collect());
+    family(&mut out, "randy_filesystem_usage_percent", "gauge", "Filesystem space used as a percent of its size",
+           keys.iter().map(|k| (labels(&[("mount_point", k)]), usage[*k].used as f64 * 100.0 / usage[*k].total as f64)).collect());
+
+    return out;
+}
+
+fn get_batteries(batteries: &Vec<(String, String)>) -> String {
+    let mut out = String::new();
+    let readings: Vec<(&String, deets::Battery)> = batteries.iter().map(|(name, path)| (name, deets::get_battery(path))).collect();
+
+    family(&mut out, "randy_battery_capacity_percent", "gauge", "Battery charge",
+           readings.iter().map(|(name, b)| (labels(&[("name", name)]), b.capacity as f64)).collect());
+    family(&mut out, "randy_battery_plugged", "gauge", "1 when the battery is not discharging",
+           readings.iter().map(|(name, b)| (labels(&[("name", name)]), match b.state { deets::BatteryState::Discharging => 0.0, _ => 1.0 })).collect());
+    family(&mut out, "randy_battery_power_watts", "gauge", "Power going in or out of the battery",
+           readings.iter().filter_map(|(name, b)| Some((labels(&[("name", name)]), b.power?))).collect());
+    family(&mut out, "randy_battery_health_percent", "gauge", "Full charge as a percent of the design capacity",
+           readings.iter().filter_map(|(name, b)| Some((labels(&[("name", name)]), b.health()?))).collect());
+
+    return out;
+}
+
+fn get_ps(ps_info: &Vec<deets::PsInfo>, top: usize) -> String {
+    let mut out = String::new();
+    let mut sorted: Vec<&deets::PsInfo> = ps_info.iter().collect();
+
+    sorted.sort_by(|a, b| b.cpu.partial_cmp(&a.cpu).unwrap_or(Ordering::Equal));
+    family(&mut out, "randy_process_cpu_percent", "gauge", "Top processes by CPU usage",
+           sorted.iter().take(top).map(|p| (labels(&[("pid", &p.pid), ("comm", &p.comm)]), p.cpu as f64)).collect());
+
+    sorted.sort_by(|a, b| b.mem.partial_cmp(&a.mem).unwrap_or(Ordering::Equal));
+    family(&mut out, "randy_process_mem_percent", "gauge", "Top processes by memory usage",
+           sorted.iter().take(top).map(|p| (labels(&[("pid", &p.pid), ("comm", &p.comm)]), p.mem as f64)).collect());
+
+    return out;
+}
+
+// The top processes need the /proc walk even without a *_consumers module
+pub fn wants_ps() -> bool {
+    return match EXPORTER.lock().unwrap().as_ref() {
+        Some(e) => e.top > 0,
+        None => false,
+    };
+}
+
+// Everything the exporter publishes has to be read whatever the modules show
+pub fn cache_wants() -> CacheWants {
+    return match EXPORTER.lock().unwrap().as_ref() {
+        Some(_) => CacheWants::all(),
+        None => CacheWants::default(),
+    };
+}
+
+// Called from modules::sample_all once the frame is sampled
+pub fn collect(frame: &Frame) {
+    let mut exporter = EXPORTER.lock().unwrap();
+    let exporter = match exporter.as_mut() {
+        Some(e) => e,
+        None => return,
+    };
+
+    let mut out = String::new();
+
+    let mut cpus = vec![(labels(&[("cpu", "all")]), deets::get_cpu_usage(-1))];
+    for i in deets::get_cpu_ids() {
+        cpus.push((labels(&[("cpu", &format!("{}", i))]), deets::get_cpu_usage(i)));
+    }
+    family(&mut out, "randy_cpu_usage_percent", "gauge", "CPU busy time over the last frame", cpus);
+
+    family(&mut out, "randy_memory_total_bytes", "gauge", "MemTotal from /proc/meminfo",
+           vec![(String::new(), frame.cache.mem("MemTotal") as f64)]);
+    family(&mut out, "randy_memory_free_bytes", "gauge", "MemFree from /proc/meminfo",
+           vec![(String::new(), frame.cache.mem("MemFree") as f64)]);
+    family(&mut out, "randy_memory_available_bytes", "gauge", "MemAvailable from /proc/meminfo",
+           vec![(String::new(), frame.cache.mem_available() as f64)]);
+    family(&mut out, "randy_swap_total_bytes", "gauge", "SwapTotal from /proc/meminfo",
+           vec![(String::new(), frame.cache.mem("SwapTotal") as f64)]);
+    family(&mut out, "randy_swap_free_bytes", "gauge", "SwapFree from /proc/meminfo",
+           vec![(String::new(), frame.cache.mem("SwapFree") as f64)]);
+
+    family(&mut out, "randy_kernel_events_total", "counter", "ctxt, intr and processes (forks) from /proc/stat",
+           counters(&frame.cache.activity, "event", &["ctxt", "intr", "processes"]));
+    family(&mut out, "randy_vmstat_total", "counter", "Paging, reclaim and OOM counters from /proc/vmstat",
+           counters(&frame.cache.vmstat, "field", &["pgfault", "pgmajfault", "pswpin", "pswpout", "pgscan", "pgsteal", "oom_kill"]));
+
+    let mut interfaces: Vec<&String> = frame.cache.net_dev.keys().collect();
+    interfaces.sort();
+    family(&mut out, "randy_network_transmit_bytes_total", "counter", "Bytes sent",
+           interfaces.iter().map(|i| (labels(&[("interface", i)]), frame.cache.net_dev[*i].0.total as f64)).collect());
+    family(&mut out, "randy_network_receive_bytes_total", "counter", "Bytes received",
+           interfaces.iter().map(|i| (labels(&[("interface", i)]), frame.cache.net_dev[*i].1.total as f64)).collect());
+
+    let mut devices: Vec<&String> = frame.cache.disk_stats.keys().collect();
+    devices.sort();
+    family(&mut out, "randy_disk_read_bytes_total", "counter", "Bytes read",
+           devices.iter().map(|d| (labels(&[("device", d)]), frame.cache.disk_stats[*d].read_bytes as f64)).collect());
+    family(&mut out, "randy_disk_written_bytes_total", "counter", "Bytes written",
+           devices.iter().map(|d| (labels(&[("device", d)]), frame.cache.disk_stats[*d].write_bytes as f64)).collect());
+
+    if exporter.mount_points.len() != 0 && frame.counter % exporter.mod_fs == 0 {
+        exporter.fs = get_fs(&exporter.mount_points);
+    }
+
+    if exporter.batteries.len() != 0 && frame.counter % exporter.mod_bat == 0 {
+        exporter.bat = get_batteries(&exporter.batteries);
+    }
+
+    if frame.top {
+        exporter.ps = get_ps(&frame.cache.ps_info, exporter.top);
+    }
+
+    out.push_str(&exporter.fs);
+    out.push_str(&exporter.bat);
+    out.push_str(&exporter.ps);
+
+    *SNAPSHOT.lock().unwrap() = out;
+}
+
+fn serve(mut stream: TcpStream) {
+    if stream.set_read_timeout(Some(CLIENT_TIMEOUT)).is_err() || stream.set_write_timeout(Some(CLIENT_TIMEOUT)).is_err() {
+        return;
+    }
+
+    let mut request_line = String::new();
+    match BufReader::new(&stream).read_line(&mut request_line) {
+        Ok(_)  => (),
+        Err(_) => return,
+    }
+
+    let response = match request_line.starts_with("GET /metrics ") {
+        true => {
+            let body = SNAPSHOT.lock().unwrap().clone();
+            format!("HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body)
+        },
+        false => String::from("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"),
+    };
+
+    let _ = stream.write_all(response.as_bytes());
+}
+
+pub fn start(config: &Yaml) {
+    let settings = &config["settings"];
+    let address = settings["metrics_address"].as_str().unwrap_or(DEFAULT_ADDRESS);
+
+    let mut mount_points = Vec::new();
+    let mut batteries = Vec::new();
+    for i in config["ui"].as_vec().unwrap_or(&Vec::new()) {
+        // a battery module without items shows every battery
+        if i["type"].as_str() == Some("battery") && i["items"].as_vec().is_none() {
+            batteries.extend(deets::get_battery_paths());
+        }
+
+        for item in i["items"].as_vec().unwrap_or(&Vec::new()) {
+            match (i["type"].as_str(), item["mount_point"].as_str(), item["name"].as_str()) {
+                (Some("filesystem"), Some(m), _) => mount_points.push(String::from(m)),
+                (Some("battery"), _, Some(n)) => batteries.push((String::from(n), deets::battery_path(item))),
+                _ => (),
+            }
+        }
+    }
+
+    let listener = match TcpListener::bind(address) {
+        Ok(l)  => l,
+        Err(e) => {
+            eprintln!("Unable to serve metrics on {}: {}", address, e);
+            return;
+        },
+    };
+
+    *EXPORTER.lock().unwrap() = Some(Exporter {
+        mount_points: mount_points,
+        batteries:    batteries,
+        top:          settings["metrics_top"].as_i64().unwrap_or(5) as usize,
+        mod_fs:       settings["mod_fs"].as_i64().unwrap_or(2) as u64,
+        mod_bat:      settings["mod_bat"].as_i64().unwrap_or(2) as u64,
+        fs:  String::new(),
+        bat: String::new(),
+        ps:  String::new(),
+    });
+
+    eprintln!("Serving metrics on http://{}/metrics", address);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(s)  => serve(s),
+                Err(_) => (),
+            }
+        }
+    });
+}
@@ -0,0 +1,223 @@
+use gtk::prelude::*;
+
+use yaml_rust::Yaml;
+
+use crate::deets::{self, Battery, BatteryState};
+use crate::format;
+use crate::modules::{Frame, Line, Module, Reading, SPACING, Spec};
+
+// Without `items` every battery in /sys/class/power_supply is shown.
+// The texts and the template can be set on the module for all of them or per item
+pub const SPEC: Spec = Spec {
+    about:         "charge, power draw, time left and health of the batteries in /sys/class/power_supply",
+    keys:          &["items", "ac", "battery_text", "pluggged_text", "charging_text", "full_text", "not_charging_text", "percent_template"],
+    required:      &[],
+    item_keys:     &["name", "path", "battery_text", "pluggged_text", "charging_text", "full_text", "not_charging_text", "percent_template"],
+    item_required: &["name"],
+};
+
+struct Row {
+    lbl_pct:    gtk::Label,
+    lbl_status: gtk::Label,
+}
+
+struct Source {
+    name: String,
+    path: String,
+    item: Yaml,
+}
+
+pub struct Batteries {
+    module:    Yaml,
+    sources:   Vec<Source>,
+    ac:        bool,
+    mod_bat:   u64,
+    rows:      Vec<Row>,
+    ac_label:  Option<gtk::Label>,
+    readings:  Vec<Battery>,
+    ac_online: Option<bool>,
+}
+
+pub fn new(item: &Yaml, settings: &Yaml) -> Box<dyn Module> {
+    let sources = match item["items"].as_vec() {
+        Some(items) => items.iter().map(|i| Source {
+            name: String::from(i["name"].as_str().unwrap()),
+            path: deets::battery_path(i),
+            item: i.clone(),
+        }).collect(),
+        None => deets::get_battery_paths().into_iter().map(|(name, path)| Source {
+            name: name,
+            path: path,
+            item: Yaml::Null,
+        }).collect(),
+    };
+
+    return Box::new(Batteries {
+        module:    item.clone(),
+        sources:   sources,
+        ac:        item["ac"].as_bool().unwrap_or(false),
+        mod_bat:   settings["mod_bat"].as_i64().unwrap_or(2) as u64,
+        rows:      Vec::new(),
+        ac_label:  None,
+        readings:  Vec::new(),
+        ac_online: None,
+    });
+}
+
+fn hours_minutes(secs: u64) -> String {
+    return match secs / 3600 {
+        0 => format!("{}m", secs / 60),
+        h => format!("{}h {:02}m", h, (secs / 60) % 60),
+    };
+}
+
+impl Batteries {
+    // the first of keys set on the item, then on the module
+    fn setting(&self, i: usize, keys: &[&str], fallback: &str) -> String {
+        for node in [&self.sources[i].item, &self.module].iter() {
+            match keys.iter().find_map(|k| node[*k].as_str()) {
+                Some(s) => return String::from(s),
+                None => (),
+            }
+        }
+
+        return String::from(fallback);
+    }
+
+    // pluggged_text is from before there were separate texts for charging, full and not charging
+    fn status(&self, i: usize, battery: &Battery) -> String {
+        return match battery.state {
+            BatteryState::Discharging => self.setting(i, &["battery_text"], "Discharging"),
+            BatteryState::Charging    => self.setting(i, &["charging_text", "pluggged_text"], "Charging"),
+            BatteryState::Full        => self.setting(i, &["full_text", "pluggged_text"], "Full"),
+            BatteryState::NotCharging | BatteryState::Unknown => self.setting(i, &["not_charging_text", "pluggged_text"], "Not charging"),
+        };
+    }
+
+    fn describe(&self, i: usize, battery: &Battery) -> String {
+        let opt = |v: Option<f64>, precision: usize| v.map(|v| format!("{:.*}", precision, v)).unwrap_or(String::new());
+
+        return format::apply(&self.setting(i, &["percent_template"], "{pct}%"), &[
+            ("pct",     format!("{}", battery.capacity)),
+            ("power",   opt(battery.power, 1)),
+            ("time",    battery.time_left().map(|d| hours_minutes(d.as_secs())).unwrap_or(String::new())),
+            ("energy",  opt(battery.energy, 1)),
+            ("full",    opt(battery.energy_full, 1)),
+            ("design",  opt(battery.energy_design, 1)),
+            ("health",  opt(battery.health(), 0)),
+            ("voltage", opt(battery.voltage, 2)),
+            ("cycles",  battery.cycles.map(|c| format!("{}", c)).unwrap_or(String::new())),
+        ]);
+    }
+
+    fn ac_text(&self) -> Option<&'static str> {
+        return match (self.ac, self.ac_online) {
+            (true, Some(true))  => Some("online"),
+            (true, Some(false)) => Some("offline"),
+            _ => None,
+        };
+    }
+}
+
+fn add_label(column: &gtk::Box, text: &str, class: &str) -> gtk::Label {
+    let label = gtk::Label::new(None);
+    label.get_style_context().add_class(class);
+    label.set_halign(gtk::Align::Start);
+    label.set_text(text);
+    column.add(&label);
+    return label;
+}
+
+impl Module for Batteries {
+    fn build(&mut self, container: &gtk::Box) {
+        container.set_orientation(gtk::Orientation::Horizontal);
+        container.get_style_context().add_class("batt");
+
+        let key_col = gtk::Box::new(gtk::Orientation::Vertical, SPACING);
+        key_col.set_hexpand(true);
+        let val_col = gtk::Box::new(gtk::Orientation::Vertical, SPACING);
+
+        for (i, source) in self.sources.iter().enumerate() {
+            add_label(&key_col, &format!("{}:", source.name), "key");
+
+            let val_box = gtk::Box::new(gtk::Orientation::Horizontal, SPACING);
+            val_box.set_halign(gtk::Align::Start);
+
+            let status_lbl = add_label(&val_box, &self.setting(i, &["battery_text"], "Discharging"), "val");
+            status_lbl.get_style_context().add_class("emoji");
+
+            let template = self.setting(i, &["percent_template"], "{pct}%");
+            let pct_lbl = add_label(&val_box, &format::apply(&template, &[("pct", String::from("000"))]), "val");
+
+            val_col.add(&val_box);
+
+            self.rows.push(Row {
+                lbl_pct:    pct_lbl,
+                lbl_status: status_lbl,
+            });
+        }
+
+        if self.ac {
+            add_label(&key_col, "AC:", "key");
+            self.ac_label = Some(add_label(&val_col, "", "val"));
+        }
+
+        container.add(&key_col);
+        container.add(&val_col);
+    }
+
+    fn sample(&mut self, frame: &Frame) {
+        if frame.counter % self.mod_bat != 0 {
+            return;
+        }
+
+        let get_battery = deets::get_battery;
+        self.readings = self.sources.iter().map(|source| timings!("battery", get_battery, &source.path)).collect();
+
+        if self.ac {
+            self.ac_online = deets::get_ac_online();
+        }
+    }
+
+    fn render(&mut self) {
+        self.rows.iter().zip(self.readings.iter()).enumerate().for_each(|(i, (row, battery))| {
+            row.lbl_status.set_text(&self.status(i, battery));
+            row.lbl_pct.set_text(&self.describe(i, battery));
+        });
+
+        match (&self.ac_label, self.ac_text()) {
+            (Some(lbl), Some(text)) => lbl.set_text(text),
+            (Some(lbl), None) => lbl.set_text("none"),
+            _ => (),
+        }
+    }
+
+    fn text(&self) -> Vec<Line> {
+        return self.readings().into_iter().map(|r| Line::Row(r.label, r.text)).collect();
+    }
+
+    fn readings(&self) -> Vec<Reading> {
+        let mut readings: Vec<Reading> = self.sources.iter().zip(self.readings.iter()).enumerate().map(|(i, (source, battery))| {
+            return Reading {
+                id:       format!("battery.{}", source.name),
+                label:    format!("{}:", source.name),
+                text:     format!("{} {}", self.status(i, battery), self.describe(i, battery)),
+                fraction: None,
+                level:    None,
+            };
+        }).collect();
+
+        match self.ac_text() {
+            Some(text) => readings.push(Reading {
+                id:       String::from("battery.ac"),
+                label:    String::from("AC:"),
+                text:     String::from(text),
+                fraction: None,
+                level:    None,
+            }),
+            None => (),
+        }
+
+        return readings;
+    }
+}
@@ -0,0 +1,284 @@
+use gtk::prelude::*;
+
+use std::cmp::Ordering;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use yaml_rust::Yaml;
+
+use crate::deets::{PsInfo, PsWants};
+use crate::format::{self, Units};
+use crate::modules::{Frame, Line, Module, SPACING, Spec};
+
+// `columns` is a list of column names or `{col: <name>, width: <chars>, align: left|right}`
+pub const SPEC: Spec = Spec {
+    about:         "top `limit` processes by cpu or memory use",
+    keys:          &["limit", "columns"],
+    required:      &[],
+    item_keys:     &[],
+    item_required: &[],
+};
+
+// other users' processes only show up when running as root
+pub const IO_SPEC: Spec = Spec {
+    about:         "top `limit` processes by bytes read and written per second",
+    keys:          &["limit", "columns", "units", "prefix"],
+    required:      &[],
+    item_keys:     &[],
+    item_required: &[],
+};
+
+// everything but these needs the extra reads of PsWants::details
+pub const CHEAP_COLUMNS: [&str; 7] = ["name", "pid", "cpu", "mem", "io", "read", "write"];
+pub const DETAIL_COLUMNS: [&str; 10] = ["cmdline", "user", "state", "nice", "threads", "rss", "swap", "start", "elapsed", "cgroup"];
+
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+struct Column {
+    kind:  String,
+    width: Option<usize>,
+    align: gtk::Align,
+}
+
+pub struct Consumers {
+    limit:     usize,
+    columns:   Vec<Column>,
+    // what processes are ranked by
+    key:       fn(&PsInfo) -> f64,
+    units:     Units,
+    io:        bool,
+    rows:      Vec<Vec<gtk::Label>>,
+    top:       Vec<Vec<String>>,
+}
+
+fn column(node: &Yaml) -> Option<Column> {
+    let kind = match node.as_str() {
+        Some(k) => String::from(k),
+        None => String::from(node["col"].as_str()?),
+    };
+
+    if !CHEAP_COLUMNS.contains(&kind.as_str()) && !DETAIL_COLUMNS.contains(&kind.as_str()) {
+        return None;
+    }
+
+    // names used to be cut at 20 characters, long command lines would push everything else out
+    let width = match node["width"].as_i64() {
+        Some(w) => Some(w.max(1) as usize),
+        None => match kind.as_str() {
+            "name" | "cgroup" => Some(20),
+            "cmdline"         => Some(40),
+            _ => None,
+        },
+    };
+
+    let text = ["name", "cmdline", "user", "state", "cgroup"].contains(&kind.as_str());
+    let align = match node["align"].as_str() {
+        Some("left")  => gtk::Align::Start,
+        Some("right") => gtk::Align::End,
+        _ => match text {
+            true  => gtk::Align::Start,
+            false => gtk::Align::End,
+        },
+    };
+
+    return Some(Column {
+        kind:  kind,
+        width: width,
+        align: align,
+    });
+}
+
+fn _new(default_columns: &[&str], key: fn(&PsInfo) -> f64, item: &Yaml) -> Consumers {
+    let defaults: Vec<Yaml> = default_columns.iter().map(|c| Yaml::from_str(c)).collect();
+
+    // the config check reports bad columns, without a single good one the defaults are shown
+    let mut columns: Vec<Column> = item["columns"].as_vec().unwrap_or(&defaults).iter().filter_map(column).collect();
+    if columns.is_empty() {
+        columns = defaults.iter().filter_map(column).collect();
+    }
+
+    return Consumers {
+        limit:     item["limit"].as_i64().unwrap_or(5).max(0) as usize,
+        columns:   columns,
+        key:       key,
+        units:     format::units(item),
+        io:        false,
+        rows:      Vec::new(),
+        top:       Vec::new(),
+    };
+}
+
+pub fn new_cpu(item: &Yaml, _settings: &Yaml) -> Box<dyn Module> {
+    return Box::new(_new(&["name", "pid", "cpu"], |p| p.cpu as f64, item));
+}
+
+pub fn new_mem(item: &Yaml, _settings: &Yaml) -> Box<dyn Module> {
+    return Box::new(_new(&["name", "pid", "mem"], |p| p.mem as f64, item));
+}
+
+pub fn new_io(item: &Yaml, _settings: &Yaml) -> Box<dyn Module> {
+    // ranked by both, shown apart
+    let mut consumers = _new(&["name", "pid", "read", "write"], |p| p.io.map(|(r, w)| r + w).unwrap_or(0.0), item);
+
+    consumers.io = true;
+    return Box::new(consumers);
+}
+
+// ps style [[dd-]hh:]mm:ss
+fn elapsed(secs: u64) -> String {
+    let (days, hours, mins, secs) = (secs / 86400, (secs / 3600) % 24, (secs / 60) % 60, secs % 60);
+    return match (days, hours) {
+        (0, 0) => format!("{:02}:{:02}", mins, secs),
+        (0, _) => format!("{:02}:{:02}:{:02}", hours, mins, secs),
+        _      => format!("{}-{:02}:{:02}:{:02}", days, hours, mins, secs),
+    };
+}
+
+// local time of day for the last 24 hours, the date before that
+fn started(ago: u64) -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let then = now.saturating_sub(ago) as libc::time_t;
+
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe { libc::localtime_r(&then, &mut tm); };
+
+    return match ago < 86400 {
+        true  => format!("{:02}:{:02}", tm.tm_hour, tm.tm_min),
+        false => format!("{}{:02}", MONTHS[tm.tm_mon.max(0).min(11) as usize], tm.tm_mday),
+    };
+}
+
+// cut by characters, comm and cmdline are UTF-8
+fn fit(text: String, width: Option<usize>) -> String {
+    return match width {
+        Some(w) if text.chars().count() > w => text.chars().take(w).collect(),
+        _ => text,
+    };
+}
+
+impl Consumers {
+    fn cell(&self, kind: &str, p: &PsInfo, uptime: u64) -> String {
+        let (read, write) = p.io.unwrap_or((0.0, 0.0));
+
+        match kind {
+            "name"  => return p.comm.clone(),
+            "pid"   => return p.pid.clone(),
+            "cpu"   => return format!("{:.1}%", p.cpu),
+            "mem"   => return format!("{:.1}%", p.mem),
+            "io"    => return format::rate(read + write, &self.units),
+            "read"  => return format::rate(read, &self.units),
+            "write" => return format::rate(write, &self.units),
+            _ => (),
+        }
+
+        let d = match &p.details {
+            Some(d) => d,
+            None => return String::new(),
+        };
+
+        return match kind {
+            "cmdline" => d.cmdline.clone(),
+            "user"    => d.user.clone(),
+            "state"   => d.state.to_string(),
+            "nice"    => format!("{}", d.nice),
+            "threads" => format!("{}", d.threads),
+            "rss"     => format::size(d.rss),
+            "swap"    => format::size(d.swap),
+            "start"   => started(uptime.saturating_sub(d.start)),
+            "elapsed" => elapsed(uptime.saturating_sub(d.start)),
+            "cgroup"  => d.cgroup.clone(),
+            _ => String::new(),
+        };
+    }
+}
+
+fn add_consumers(columns: &[Column], limit: usize, container: &gtk::Box, rows: &mut Vec<Vec<gtk::Label>>) {
+    container.get_style_context().add_class("top-frame");
+    container.set_orientation(gtk::Orientation::Horizontal);
+
+    let boxes: Vec<gtk::Box> = columns.iter().map(|_| gtk::Box::new(gtk::Orientation::Vertical, SPACING)).collect();
+
+    let add_label = |i: usize, text: &str| {
+        let label = gtk::Label::new(None);
+        label.set_text(text);
+        label.set_halign(columns[i].align);
+        // a fixed width label is wider than its text, which then needs aligning within it
+        // set_xalign needs gtk 3.16, GtkMisc has had the property all along
+        let xalign: f32 = match columns[i].align {
+            gtk::Align::End => 1.0,
+            _ => 0.0,
+        };
+        label.set_property("xalign", &xalign).unwrap();
+
+        match columns[i].width {
+            Some(w) => label.set_width_chars(w as i32),
+            None => (),
+        }
+
+        boxes[i].add(&label);
+        return label;
+    };
+
+    for (i, col) in columns.iter().enumerate() {
+        add_label(i, &col.kind.to_uppercase());
+    }
+
+    for _ in 0..limit {
+        rows.push((0..columns.len()).map(|i| add_label(i, "")).collect());
+    }
+
+    // the first column takes up the slack
+    for (i, column_box) in boxes.iter().enumerate() {
+        container.pack_start(column_box, i == 0, i == 0, 0);
+    }
+}
+
+impl Module for Consumers {
+    fn build(&mut self, container: &gtk::Box) {
+        add_consumers(&self.columns, self.limit, container, &mut self.rows);
+    }
+
+    fn wants_ps(&self) -> bool {
+        return true;
+    }
+
+    fn ps_wants(&self) -> PsWants {
+        return PsWants {
+            io:      self.io || self.columns.iter().any(|c| ["io", "read", "write"].contains(&c.kind.as_str())),
+            details: self.columns.iter().any(|c| DETAIL_COLUMNS.contains(&c.kind.as_str())),
+        };
+    }
+
+    fn sample(&mut self, frame: &Frame) {
+        if !frame.top { return; }
+
+        let key = self.key;
+        let mut ps_info: Vec<&PsInfo> = frame.cache.ps_info.iter().collect();
+        ps_info.sort_by(|a, b| key(b).partial_cmp(&key(a)).unwrap_or(Ordering::Equal));
+
+        let uptime = frame.cache.uptime();
+        self.top = ps_info.iter()
+            .take(self.limit)
+            .map(|p| self.columns.iter().map(|c| fit(self.cell(&c.kind, p, uptime), c.width)).collect())
+            .collect();
+    }
+
+    fn render(&mut self) {
+        for (i, labels) in self.rows.iter().enumerate() {
+            for (j, label) in labels.iter().enumerate() {
+                label.set_text(self.top.get(i).and_then(|row| row.get(j)).map(|s| s.as_str()).unwrap_or(""));
+            }
+        }
+    }
+
+    fn text(&self) -> Vec<Line> {
+        let mut lines = vec![
+            Line::Cols(self.columns.iter().map(|c| c.kind.to_uppercase()).collect()),
+        ];
+
+        for row in self.top.iter() {
+            lines.push(Line::Cols(row.clone()));
+        }
+
+        return lines;
+    }
+}
@@ -0,0 +1,158 @@
+use gtk::prelude::*;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use yaml_rust::Yaml;
+
+use crate::deets::{self, CPU_TIMES};
+use crate::modules::{Frame, Line, Module, Reading, Spec};
+
+// `fields` are from deets::CPU_TIMES, `idx` picks one CPU instead of all of them
+pub const SPEC: Spec = Spec {
+    about:         "where CPU time goes (user, system, iowait, steal...) as a stacked bar",
+    keys:          &["fields", "idx"],
+    required:      &[],
+    item_keys:     &[],
+    item_required: &[],
+};
+
+const BAR_HEIGHT: i32 = 10;
+
+// colors of the stacked bar, in CPU_TIMES order. idle is left empty
+const COLORS: [&str; 9] = ["#7fb3ff", "#a5d6a7", "#ff8a80", "", "#ffd54f", "#ce93d8", "#b39ddb", "#ff7043", "#80cbc4"];
+
+pub struct CpuTimes {
+    cpu:    i32,
+    // indexes into CPU_TIMES
+    fields: Vec<usize>,
+    legend: Option<gtk::Label>,
+    bar:    Option<gtk::DrawingArea>,
+    times:  Rc<RefCell<[f64; 9]>>,
+}
+
+pub fn new(item: &Yaml, _settings: &Yaml) -> Box<dyn Module> {
+    let fields = match item["fields"].as_vec() {
+        Some(f) => f.iter()
+            .filter_map(|f| f.as_str())
+            .filter_map(|f| CPU_TIMES.iter().position(|t| *t == f))
+            .collect(),
+        None => vec![0, 1, 2, 4, 5, 6, 7],
+    };
+
+    return Box::new(CpuTimes {
+        cpu:    item["idx"].as_i64().unwrap_or(-1) as i32,
+        fields: fields,
+        legend: None,
+        bar:    None,
+        times:  Rc::new(RefCell::new([0.0; 9])),
+    });
+}
+
+impl CpuTimes {
+    // busy time of the fields shown, guest is already part of user
+    fn busy(&self) -> f64 {
+        let times = self.times.borrow();
+        let busy: f64 = self.fields.iter().filter(|i| **i != 3 && **i != 8).map(|i| times[*i]).sum();
+        return (busy / 100.0).min(1.0);
+    }
+
+    fn describe(&self) -> String {
+        let times = self.times.borrow();
+        return self.fields.iter()
+            .map(|i| format!("{} {:.0}%", CPU_TIMES[*i], times[*i]))
+            .collect::<Vec<String>>()
+            .join(" ");
+    }
+}
+
+impl Module for CpuTimes {
+    fn build(&mut self, container: &gtk::Box) {
+        container.set_orientation(gtk::Orientation::Vertical);
+
+        let legend = gtk::Label::new(None);
+        legend.set_halign(gtk::Align::Start);
+        legend.set_line_wrap(true);
+        legend.get_style_context().add_class("key");
+        container.add(&legend);
+
+        let bar = gtk::DrawingArea::new();
+        bar.set_hexpand(true);
+        bar.set_size_request(-1, BAR_HEIGHT);
+        bar.get_style_context().add_class("graph");
+
+        let times = self.times.clone();
+        let fields = self.fields.clone();
+        bar.connect_draw(move |area, cr| {
+            let times = times.borrow();
+            let width = area.get_allocated_width() as f64;
+            let height = area.get_allocated_height() as f64;
+
+            // left to right in the order of `fields`
+            let mut x = 0.0;
+            for i in fields.iter().filter(|i| **i != 3 && **i != 8) {
+                let w = width * (times[*i] / 100.0).max(0.0).min(1.0);
+                let c: gdk::RGBA = COLORS[*i].parse().unwrap();
+
+                cr.set_source_rgba(c.red, c.green, c.blue, c.alpha);
+                cr.rectangle(x, 0.0, w, height);
+                cr.fill();
+                x += w;
+            }
+
+            return gtk::Inhibit(false);
+        });
+        container.add(&bar);
+
+        self.legend = Some(legend);
+        self.bar = Some(bar);
+    }
+
+    fn sample(&mut self, _frame: &Frame) {
+        self.times.replace(deets::get_cpu_times(self.cpu));
+    }
+
+    fn render(&mut self) {
+        let times = self.times.borrow();
+
+        match &self.legend {
+            Some(legend) => legend.set_markup(&self.fields.iter().map(|i| {
+                return match COLORS[*i] {
+                    "" => format!("{} {:.0}%", CPU_TIMES[*i], times[*i]),
+                    c => format!("<span foreground=\"{}\">■</span> {} {:.0}%", c, CPU_TIMES[*i], times[*i]),
+                };
+            }).collect::<Vec<String>>().join(" ")),
+            None => (),
+        }
+
+        match &self.bar {
+            Some(bar) => bar.queue_draw(),
+            None => (),
+        }
+    }
+
+    fn text(&self) -> Vec<Line> {
+        return vec![
+            Line::Row(String::new(), self.describe()),
+            Line::Bar(self.busy()),
+        ];
+    }
+
+    fn readings(&self) -> Vec<Reading> {
+        let times = self.times.borrow();
+        let prefix = match self.cpu {
+            -1 => String::from("cpu_time"),
+            n => format!("cpu_time.cpu{:02}", n),
+        };
+
+        return self.fields.iter().map(|i| {
+            return Reading {
+                id:       format!("{}.{}", prefix, CPU_TIMES[*i]),
+                label:    String::from(CPU_TIMES[*i]),
+                text:     format!("{:.0}%", times[*i]),
+                fraction: Some(times[*i] / 100.0),
+                level:    None,
+            };
+        }).collect();
+    }
+}
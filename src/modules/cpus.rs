@@ -0,0 +1,472 @@
+use gtk::prelude::*;
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use yaml_rust::Yaml;
+
+use crate::deets;
+use crate::modules::{Frame, Line, Module, Reading, SPACING, Spec, update_bar};
+use crate::modules::graph::{self, Scale};
+use crate::modules::system::Gauge;
+
+// `func: cpus` shows up in older configs, it is ignored
+pub const SPEC: Spec = Spec {
+    about:         "usage and clock of every CPU",
+    keys:          &["split", "topology", "style", "per_row", "widget", "history", "scale", "max", "freq_bar", "governor", "func"],
+    required:      &[],
+    item_keys:     &[],
+    item_required: &[],
+};
+
+struct Cpu {
+    id: i32,
+    mhz: gtk::Label,
+    gauge: Gauge,
+    freq_bar: Option<gtk::ProgressBar>,
+    pct_label: gtk::Label,
+}
+
+// one square of the heatmap
+struct Tile {
+    id:    i32,
+    area:  gtk::DrawingArea,
+    usage: Rc<Cell<f64>>,
+}
+
+const TILE_HEIGHT: i32 = 14;
+
+// one line of the layout
+enum Row {
+    Package(i32),
+    // a single CPU, or all the SMT siblings of a core
+    Cpus(Vec<i32>),
+}
+
+pub struct Cpus {
+    split: bool,
+    topology: bool,
+    heatmap: bool,
+    // heatmap tiles on each line
+    per_row: usize,
+    item:  Yaml,
+    style: graph::Style,
+    container: Option<gtk::Box>,
+    cpus:  Vec<Cpu>,
+    tiles: Vec<Tile>,
+    governor: Option<gtk::Label>,
+    // the online CPUs, the layout is redone when they change
+    online: Vec<i32>,
+    // set by sample on hotplug, render redoes the widgets
+    rebuild: bool,
+    topo:  Vec<deets::CpuTopo>,
+    mhz:   HashMap<i32, u16>,
+    freqs: Option<HashMap<i32, u32>>,
+    hw_max: HashMap<i32, u32>,
+    // only read with `governor: true`
+    policies: HashMap<i32, deets::CpuPolicy>,
+    mod_policy: u64,
+    usage: HashMap<i32, f64>,
+}
+
+pub fn new(item: &Yaml, settings: &Yaml) -> Box<dyn Module> {
+    return Box::new(Cpus {
+        split: item["split"].as_bool().unwrap_or(false),
+        topology: item["topology"].as_bool().unwrap_or(false),
+        heatmap: item["style"].as_str() == Some("heatmap"),
+        per_row: item["per_row"].as_i64().unwrap_or(16).max(1) as usize,
+        item:  item.clone(),
+        style: graph::style(settings),
+        container: None,
+        cpus:  Vec::new(),
+        tiles: Vec::new(),
+        governor: None,
+        online: Vec::new(),
+        rebuild: false,
+        topo:  Vec::new(),
+        mhz:   HashMap::new(),
+        freqs: None,
+        hw_max: HashMap::new(),
+        policies: HashMap::new(),
+        mod_policy: settings["mod_policy"].as_i64().unwrap_or(2) as u64,
+        usage: HashMap::new(),
+    });
+}
+
+impl Cpus {
+    // governor, energy performance preference and scaling range, every distinct one
+    fn policy(&self) -> Option<String> {
+        self.freqs.as_ref()?;
+
+        let mut parts: Vec<String> = Vec::new();
+        for policy in self.online.iter().filter_map(|id| self.policies.get(id)) {
+            let part = match &policy.epp {
+                Some(epp) => format!("{} {} {}-{} MHz", policy.governor, epp, policy.min, policy.max),
+                None => format!("{} {}-{} MHz", policy.governor, policy.min, policy.max),
+            };
+
+            if !parts.contains(&part) {
+                parts.push(part);
+            }
+        }
+
+        return Some(parts.join(", "));
+    }
+
+    // how close to the fastest it can go each CPU is clocked
+    fn freq_fraction(&self, id: i32) -> Option<f64> {
+        let cur = self.freqs.as_ref()?.get(&id)?;
+        return match self.hw_max.get(&id)? {
+            0 => None,
+            m => Some((*cur as f64 / *m as f64).min(1.0)),
+        };
+    }
+
+    fn label(&self, id: i32) -> String {
+        return match self.topo.iter().find(|t| t.id == id).and_then(|t| t.kind) {
+            Some(kind) => format!("CPU{:02} {}", id, kind),
+            None => format!("CPU{:02}", id),
+        };
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        if !self.topology {
+            return self.online.iter().map(|id| Row::Cpus(vec![*id])).collect();
+        }
+
+        let mut packages: Vec<i32> = self.topo.iter().map(|t| t.package).collect();
+        packages.sort();
+        packages.dedup();
+
+        let mut rows = Vec::new();
+        for package in packages.iter() {
+            if packages.len() > 1 {
+                rows.push(Row::Package(*package));
+            }
+
+            // cores in the order of their first CPU, siblings next to each other
+            let mut cores: Vec<(i32, Vec<i32>)> = Vec::new();
+            for t in self.topo.iter().filter(|t| t.package == *package) {
+                match cores.iter_mut().find(|(core, _)| *core == t.core) {
+                    Some((_, ids)) => ids.push(t.id),
+                    None => cores.push((t.core, vec![t.id])),
+                }
+            }
+
+            rows.extend(cores.into_iter().map(|(_, ids)| Row::Cpus(ids)));
+        }
+
+        return rows;
+    }
+
+    fn add_cpu(&self, id: i32) -> (gtk::Box, Cpu) {
+        let vbox = gtk::Box::new(gtk::Orientation::Vertical, SPACING);
+        vbox.get_style_context().add_class("row");
+        vbox.set_hexpand(true);
+
+        let line_box = gtk::Box::new(gtk::Orientation::Horizontal, SPACING);
+
+        let key = gtk::Label::new(None);
+        key.get_style_context().add_class("key");
+        key.set_text(&self.label(id));
+
+        let val = gtk::Label::new(None);
+        val.get_style_context().add_class("val");
+
+        let pct = gtk::Label::new(None);
+        pct.get_style_context().add_class("val");
+        pct.get_style_context().add_class("pct");
+        pct.set_justify(gtk::Justification::Right);
+        pct.set_halign(gtk::Align::End);
+
+        line_box.pack_start(&key, true, true, 0);
+        line_box.pack_start(&val, true, true, 0);
+        line_box.pack_start(&pct, true, true, 0);
+        vbox.add(&line_box);
+
+        let gauge = match self.item["widget"].as_str() {
+            Some("graph") => {
+                let graph = graph::new(&self.item, &self.style);
+                vbox.add(graph.widget());
+                Gauge::Graph(graph)
+            },
+            _ => {
+                let progress = gtk::ProgressBar::new();
+                progress.set_hexpand(true);
+                progress.get_style_context().add_class("cpus-progress");
+                progress.set_sensitive(false);
+                vbox.add(&progress);
+                Gauge::Bar(progress)
+            },
+        };
+
+        let freq_bar = match self.item["freq_bar"].as_bool().unwrap_or(false) {
+            true => {
+                let progress = gtk::ProgressBar::new();
+                progress.set_hexpand(true);
+                progress.get_style_context().add_class("cpus-freq");
+                progress.set_sensitive(false);
+                vbox.add(&progress);
+                Some(progress)
+            },
+            false => None,
+        };
+
+        return (vbox, Cpu {
+            id: id,
+            mhz: val,
+            gauge: gauge,
+            freq_bar: freq_bar,
+            pct_label: pct,
+        });
+    }
+
+    // every CPU as a small square in the bar colors, for machines with too many for a line each
+    fn add_heatmap(&mut self, inner_box: &gtk::Box) {
+        let grid = gtk::Grid::new();
+        grid.set_column_homogeneous(true);
+        grid.set_column_spacing(2);
+        grid.set_row_spacing(2);
+        grid.get_style_context().add_class("heatmap");
+
+        let ids: Vec<i32> = self.rows().into_iter().flat_map(|row| match row {
+            Row::Cpus(ids) => ids,
+            Row::Package(_) => Vec::new(),
+        }).collect();
+
+        for (i, id) in ids.into_iter().enumerate() {
+            let area = gtk::DrawingArea::new();
+            area.set_hexpand(true);
+            area.set_size_request(-1, TILE_HEIGHT);
+
+            let usage = Rc::new(Cell::new(0.0));
+            let current = usage.clone();
+            let style = self.style;
+            area.connect_draw(move |area, cr| {
+                let fraction = current.get();
+                let c = style.color(fraction);
+
+                // idle CPUs fade into the background, busy ones get the full color
+                cr.set_source_rgba(c.red, c.green, c.blue, c.alpha * (0.15 + 0.85 * fraction));
+                cr.rectangle(0.0, 0.0, area.get_allocated_width() as f64, area.get_allocated_height() as f64);
+                cr.fill();
+
+                return gtk::Inhibit(false);
+            });
+
+            grid.attach(&area, (i % self.per_row) as i32, (i / self.per_row) as i32, 1, 1);
+            self.tiles.push(Tile { id: id, area: area, usage: usage });
+        }
+
+        inner_box.add(&grid);
+    }
+
+    // (re)builds the widgets for the CPUs online right now
+    fn add_cpus(&mut self, inner_box: &gtk::Box) {
+        inner_box.get_children().iter().for_each(|child| inner_box.remove(child));
+        self.cpus.clear();
+        self.tiles.clear();
+        self.governor = None;
+
+        if self.item["governor"].as_bool().unwrap_or(false) {
+            let governor = gtk::Label::new(None);
+            governor.get_style_context().add_class("val");
+            governor.set_halign(gtk::Align::Start);
+            inner_box.add(&governor);
+            self.governor = Some(governor);
+        }
+
+        if self.heatmap {
+            self.add_heatmap(inner_box);
+            inner_box.show_all();
+            return;
+        }
+
+        let columns = [
+            gtk::Box::new(gtk::Orientation::Vertical, SPACING),
+            gtk::Box::new(gtk::Orientation::Vertical, SPACING),
+        ];
+        columns.iter().for_each(|c| c.get_style_context().add_class("innerbox"));
+
+        let rows = self.rows();
+        let half = (rows.len() + 1) / 2;
+
+        for (i, row) in rows.iter().enumerate() {
+            // Determine which box to use based on index
+            let column = &columns[if self.split && i >= half { 1 } else { 0 }];
+
+            match row {
+                Row::Package(package) => {
+                    let lbl = gtk::Label::new(None);
+                    lbl.get_style_context().add_class("key");
+                    lbl.set_halign(gtk::Align::Start);
+                    lbl.set_text(&format!("Package {}", package));
+                    column.add(&lbl);
+                },
+                Row::Cpus(ids) => {
+                    let siblings = gtk::Box::new(gtk::Orientation::Horizontal, SPACING);
+                    siblings.set_homogeneous(true);
+
+                    for id in ids.iter() {
+                        let (cell, cpu) = self.add_cpu(*id);
+                        siblings.add(&cell);
+                        self.cpus.push(cpu);
+                    }
+
+                    column.add(&siblings);
+                },
+            }
+        }
+
+        if self.split {
+            let split_box = gtk::Box::new(gtk::Orientation::Horizontal, SPACING);
+            split_box.add(&columns[0]);
+            split_box.add(&columns[1]);
+            inner_box.add(&split_box);
+        } else {
+            inner_box.add(&columns[0]);
+        }
+
+        inner_box.show_all();
+    }
+}
+
+impl Module for Cpus {
+    fn build(&mut self, container: &gtk::Box) {
+        self.online = deets::get_cpu_ids();
+        self.topo = deets::get_topology(&self.online);
+        self.hw_max = deets::get_cpu_hw_max(&self.online);
+        self.container = Some(container.clone());
+        self.add_cpus(container);
+    }
+
+    fn sample(&mut self, frame: &Frame) {
+        let online = deets::get_cpu_ids();
+        let hotplug = online != self.online;
+        if hotplug {
+            self.online = online;
+            self.topo = deets::get_topology(&self.online);
+            self.hw_max = deets::get_cpu_hw_max(&self.online);
+            self.rebuild = true;
+        }
+
+        let get_freqs = deets::get_cpu_freqs;
+        self.freqs = timings!("cpu_freqs", get_freqs, &self.online);
+
+        // the policy only changes when someone changes it
+        if self.item["governor"].as_bool().unwrap_or(false) && (hotplug || self.policies.is_empty() || frame.counter % self.mod_policy == 0) {
+            let get_policies = deets::get_cpu_policies;
+            self.policies = timings!("cpu_policies", get_policies, &self.online);
+        }
+
+        self.mhz = match &self.freqs {
+            Some(freqs) => freqs.iter().map(|(id, f)| (*id, *f as u16)).collect(),
+            None => {
+                // /proc/cpuinfo only lists the online CPUs, in order
+                let get_mhz = deets::get_cpu_mhz;
+                self.online.iter().cloned().zip(timings!("cpu_mhz", get_mhz)).collect()
+            },
+        };
+
+        self.usage = self.online.iter().map(|id| (*id, deets::get_cpu_usage(*id))).collect();
+    }
+
+    fn render(&mut self) {
+        if self.rebuild {
+            self.rebuild = false;
+            match self.container.clone() {
+                Some(container) => self.add_cpus(&container),
+                None => (),
+            }
+        }
+
+        self.cpus.iter().for_each(|cpu| {
+            let usage = *self.usage.get(&cpu.id).unwrap_or(&0.0);
+
+            match self.mhz.get(&cpu.id) {
+                Some(mhz) => cpu.mhz.set_text(&format!("{:04.0} MHz", mhz)),
+                None => (),
+            }
+
+            match &cpu.gauge {
+                Gauge::Bar(bar) => update_bar(bar, usage / 100.0),
+                Gauge::Graph(graph) => graph.push(usage / 100.0, Scale::Fixed(1.0)),
+            }
+            cpu.pct_label.set_text(&format!("{:.0}%", usage));
+
+            match (&cpu.freq_bar, self.freq_fraction(cpu.id)) {
+                (Some(bar), Some(f)) => bar.set_fraction(f),
+                _ => (),
+            }
+        });
+
+        self.tiles.iter().for_each(|tile| {
+            let usage = *self.usage.get(&tile.id).unwrap_or(&0.0);
+            let mhz = match self.mhz.get(&tile.id) {
+                Some(m) => format!("{:04.0} MHz ", m),
+                None => String::new(),
+            };
+
+            tile.usage.set((usage / 100.0).max(0.0).min(1.0));
+            tile.area.set_tooltip_text(Some(&format!("{}\n{}{:.0}%", self.label(tile.id), mhz, usage)));
+            tile.area.queue_draw();
+        });
+
+        match (&self.governor, self.policy()) {
+            (Some(lbl), Some(policy)) => lbl.set_text(&policy),
+            (Some(lbl), None) => lbl.set_text("no cpufreq"),
+            _ => (),
+        }
+    }
+
+    fn text(&self) -> Vec<Line> {
+        let mut lines = Vec::new();
+
+        match (self.item["governor"].as_bool(), self.policy()) {
+            (Some(true), Some(policy)) => lines.push(Line::Row(String::from("Policy"), policy)),
+            _ => (),
+        }
+
+        for row in self.rows() {
+            let ids = match row {
+                Row::Package(package) => {
+                    lines.push(Line::Row(format!("Package {}", package), String::new()));
+                    continue;
+                },
+                Row::Cpus(ids) => ids,
+            };
+
+            for id in ids {
+                let usage = *self.usage.get(&id).unwrap_or(&0.0);
+                let mhz = match self.mhz.get(&id) {
+                    Some(m) => format!("{:04.0} MHz", m),
+                    None => String::new(),
+                };
+
+                lines.push(Line::Row(self.label(id), format!("{} {:>4}", mhz, format!("{:.0}%", usage))));
+                lines.push(Line::Bar(usage / 100.0));
+
+                match (self.item["freq_bar"].as_bool(), self.freq_fraction(id)) {
+                    (Some(true), Some(f)) => lines.push(Line::Bar(f)),
+                    _ => (),
+                }
+            }
+        }
+
+        return lines;
+    }
+
+    fn readings(&self) -> Vec<Reading> {
+        return self.online.iter().map(|id| {
+            let usage = *self.usage.get(id).unwrap_or(&0.0);
+            return Reading {
+                id:       format!("cpu{:02}", id),
+                label:    self.label(*id),
+                text:     format!("{:.0}%", usage),
+                fraction: Some(usage / 100.0),
+                level:    None,
+            };
+        }).collect();
+    }
+}
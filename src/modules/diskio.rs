@@ -0,0 +1,222 @@
+use gtk::prelude::*;
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use yaml_rust::Yaml;
+
+use crate::deets::{self, CacheWants, DiskStat};
+use crate::format;
+use crate::modules::{Frame, Line, Module, Reading, SPACING, Spec, update_bar};
+use crate::modules::graph::{self, Graph, Scale};
+
+// each item needs a `device` (as named in /proc/diskstats) or a `mount_point`
+pub const SPEC: Spec = Spec {
+    about:         "read/write rate, IOPS and utilization of the given block devices",
+    keys:          &["items", "units", "prefix"],
+    required:      &[],
+    item_keys:     &["text", "device", "mount_point", "val", "widget", "history", "scale", "max"],
+    item_required: &["text"],
+};
+
+struct Row {
+    val:   gtk::Label,
+    bar:   gtk::ProgressBar,
+    graph: Option<Graph>,
+}
+
+struct Io {
+    read:       f64,
+    write:      f64,
+    read_iops:  f64,
+    write_iops: f64,
+    util:       f64,
+}
+
+pub struct DiskIo {
+    items:   Vec<Yaml>,
+    devices: Vec<Option<String>>,
+    mod_fs:  u64,
+    units:   format::Units,
+    style:   graph::Style,
+    rows:    Vec<Row>,
+    last:    HashMap<String, DiskStat>,
+    last_instant: Instant,
+    io:      HashMap<String, Io>,
+}
+
+pub fn new(item: &Yaml, settings: &Yaml) -> Box<dyn Module> {
+    let items = item["items"].as_vec().unwrap_or(&Vec::new()).clone();
+    let devices = items.iter().map(|item| {
+        let device = resolve(item);
+        match (&device, item["mount_point"].as_str()) {
+            (None, Some(mount_point)) => eprintln!("Nothing mounted on {} yet, diskio will look again", mount_point),
+            _ => (),
+        }
+        return device;
+    }).collect();
+
+    return Box::new(DiskIo {
+        items:   items,
+        devices: devices,
+        mod_fs:  settings["mod_fs"].as_i64().unwrap_or(2) as u64,
+        units:   format::units(item),
+        style:   graph::style(settings),
+        rows:    Vec::new(),
+        last:    HashMap::new(),
+        last_instant: Instant::now(),
+        io:      HashMap::new(),
+    });
+}
+
+fn resolve(item: &Yaml) -> Option<String> {
+    return match (item["device"].as_str(), item["mount_point"].as_str()) {
+        (Some(device), _) => Some(String::from(device)),
+        (None, Some(mount_point)) => deets::get_mount_device(mount_point),
+        _ => None,
+    };
+}
+
+impl DiskIo {
+    fn describe(&self, i: usize) -> Option<(String, &Io)> {
+        let io = self.io.get(self.devices[i].as_ref()?)?;
+        let template = self.items[i]["val"].as_str().unwrap_or("R {read} W {write} {iops} IOPS");
+        let text = format::apply(template, &[
+            ("read",       format::rate(io.read, &self.units)),
+            ("write",      format::rate(io.write, &self.units)),
+            ("iops",       format!("{:.0}", io.read_iops + io.write_iops)),
+            ("read_iops",  format!("{:.0}", io.read_iops)),
+            ("write_iops", format!("{:.0}", io.write_iops)),
+            ("util",       format!("{:.0}", io.util * 100.0)),
+        ]);
+
+        return Some((text, io));
+    }
+}
+
+fn add_diskio(container: &gtk::Box, items: &Vec<Yaml>, style: &graph::Style, rows: &mut Vec<Row>) {
+    container.set_orientation(gtk::Orientation::Vertical);
+
+    for item in items {
+        let line_box = gtk::Box::new(gtk::Orientation::Horizontal, SPACING);
+
+        let text = gtk::Label::new(None);
+        text.get_style_context().add_class("key");
+        text.set_text(item["text"].as_str().unwrap());
+        line_box.add(&text);
+
+        let val = gtk::Label::new(None);
+        val.set_halign(gtk::Align::End);
+        val.get_style_context().add_class("val");
+        line_box.pack_start(&val, true, true, 0);
+        container.add(&line_box);
+
+        let bar = gtk::ProgressBar::new();
+        bar.set_hexpand(true);
+        bar.set_sensitive(false);
+        container.add(&bar);
+
+        let graph = match item["widget"].as_str() {
+            Some("graph") => {
+                let graph = graph::dual(item, style);
+                container.add(graph.widget());
+                Some(graph)
+            },
+            _ => None,
+        };
+
+        rows.push(Row { val: val, bar: bar, graph: graph });
+    }
+}
+
+impl Module for DiskIo {
+    fn build(&mut self, container: &gtk::Box) {
+        add_diskio(container, &self.items, &self.style, &mut self.rows);
+    }
+
+    fn cache_wants(&self) -> CacheWants {
+        return CacheWants { disk_stats: true, ..CacheWants::default() };
+    }
+
+    fn sample(&mut self, frame: &Frame) {
+        // a mount point that wasn't there at startup (a USB disk, an automount) is looked up with the filesystems
+        if frame.counter % self.mod_fs == 0 {
+            for (i, item) in self.items.iter().enumerate() {
+                if self.devices[i].is_none() {
+                    self.devices[i] = resolve(item);
+                }
+            }
+        }
+
+        let secs = self.last_instant.elapsed().as_secs_f64();
+        self.last_instant = Instant::now();
+
+        for device in self.devices.iter().flatten() {
+            let curr = match frame.cache.disk_stats.get(device) {
+                Some(s) => *s,
+                None => continue,
+            };
+
+            let last = *self.last.get(device).unwrap_or(&curr);
+            self.last.insert(device.clone(), curr);
+            if secs <= 0.0 {
+                continue;
+            }
+
+            let per_sec = |now: u64, then: u64| now.saturating_sub(then) as f64 / secs;
+            self.io.insert(device.clone(), Io {
+                read:       per_sec(curr.read_bytes, last.read_bytes),
+                write:      per_sec(curr.write_bytes, last.write_bytes),
+                read_iops:  per_sec(curr.reads, last.reads),
+                write_iops: per_sec(curr.writes, last.writes),
+                util:       (per_sec(curr.io_ticks, last.io_ticks) / 1000.0).min(1.0),
+            });
+        }
+    }
+
+    fn render(&mut self) {
+        self.rows.iter().enumerate().for_each(|(i, row)| {
+            match self.describe(i) {
+                Some((text, io)) => {
+                    row.val.set_text(&text);
+                    update_bar(&row.bar, io.util);
+
+                    match &row.graph {
+                        Some(graph) => graph.push_dual(io.read, io.write, Scale::Auto),
+                        None => (),
+                    }
+                },
+                None => (),
+            }
+        });
+    }
+
+    fn text(&self) -> Vec<Line> {
+        let mut lines = Vec::new();
+
+        for (i, item) in self.items.iter().enumerate() {
+            match self.describe(i) {
+                Some((text, io)) => {
+                    lines.push(Line::Row(String::from(item["text"].as_str().unwrap()), text));
+                    lines.push(Line::Bar(io.util));
+                },
+                None => (),
+            }
+        }
+
+        return lines;
+    }
+
+    fn readings(&self) -> Vec<Reading> {
+        return self.items.iter().enumerate().filter_map(|(i, item)| {
+            let (text, io) = self.describe(i)?;
+            return Some(Reading {
+                id:       format!("diskio.{}", self.devices[i].as_ref()?),
+                label:    String::from(item["text"].as_str().unwrap()),
+                text:     text,
+                fraction: Some(io.util),
+                level:    None,
+            });
+        }).collect();
+    }
+}
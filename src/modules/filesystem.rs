@@ -0,0 +1,158 @@
+use gtk::prelude::*;
+
+use std::collections::HashMap;
+
+use yaml_rust::Yaml;
+
+use crate::deets;
+use crate::format;
+use crate::modules::{Frame, Line, Module, Reading, SPACING, Spec, update_bar};
+
+pub const SPEC: Spec = Spec {
+    about:         "usage of the given mount points",
+    keys:          &["items"],
+    required:      &[],
+    item_keys:     &["text", "mount_point", "val"],
+    item_required: &["text", "mount_point"],
+};
+
+pub struct FileSystem {
+    items:  Vec<Yaml>,
+    mod_fs: u64,
+    rows:   HashMap<String, (gtk::Label, gtk::ProgressBar)>,
+    usage:  HashMap<String, deets::FileSystemUsage>,
+    templates: HashMap<String, String>,
+}
+
+pub fn new(item: &Yaml, settings: &Yaml) -> Box<dyn Module> {
+    let items = item["items"].as_vec().unwrap_or(&Vec::new()).clone();
+    let templates = items.iter().map(|item| {
+        return (String::from(item["mount_point"].as_str().unwrap()),
+                String::from(item["val"].as_str().unwrap_or("{used} / {total} {pct}%")));
+    }).collect();
+
+    return Box::new(FileSystem {
+        items:  items,
+        mod_fs: settings["mod_fs"].as_i64().unwrap_or(2) as u64,
+        rows:   HashMap::new(),
+        usage:  HashMap::new(),
+        templates: templates,
+    });
+}
+
+impl FileSystem {
+    fn describe(&self, mount_point: &str) -> Option<(String, f64)> {
+        let v = self.usage.get(mount_point)?;
+        let fraction = v.used as f64 / v.total as f64;
+        let text = format::apply(&self.templates[mount_point], &[
+            ("used",  format::size(v.used)),
+            ("total", format::size(v.total)),
+            ("free",  format::size(v.total - v.used)),
+            ("pct",   format!("{:.0}", fraction * 100.0)),
+        ]);
+
+        return Some((text, fraction));
+    }
+}
+
+fn add_filesystem(container: &gtk::Box, items: &Vec<Yaml>, stash: &mut HashMap<String, (gtk::Label, gtk::ProgressBar)>) {
+    container.set_orientation(gtk::Orientation::Vertical);
+
+    fn _add_item(container: &gtk::Box, item: &Yaml, stash: Option<&mut HashMap<String, (gtk::Label, gtk::ProgressBar)>>) {
+        let columns = [
+            gtk::Box::new(gtk::Orientation::Vertical, SPACING),
+            gtk::Box::new(gtk::Orientation::Vertical, SPACING),
+        ];
+
+        let wrapper = gtk::Box::new(gtk::Orientation::Horizontal, SPACING);
+        let text = gtk::Label::new(None);
+        text.get_style_context().add_class("key");
+        text.set_text(item["text"].as_str().unwrap());
+        columns[0].add(&text);
+
+        let space = gtk::Label::new(None);
+        space.set_halign(gtk::Align::End);
+        space.get_style_context().add_class("val");
+        columns[1].add(&space);
+
+        wrapper.add(&columns[0]);
+        wrapper.pack_start(&columns[1], true, true, 0);
+        container.add(&wrapper);
+
+        match stash {
+            Some(s) => {
+                let progress = gtk::ProgressBar::new();
+                progress.set_hexpand(true);
+                progress.set_sensitive(false);
+
+                container.add(&progress);
+                s.insert(String::from(item["mount_point"].as_str().unwrap()), (space, progress));
+            },
+            None => (),
+        }
+    }
+
+    for item in items {
+        _add_item(container, item, Some(stash));
+    }
+}
+
+impl Module for FileSystem {
+    fn build(&mut self, container: &gtk::Box) {
+        add_filesystem(container, &self.items, &mut self.rows);
+    }
+
+    fn sample(&mut self, frame: &Frame) {
+        if self.items.len() == 0 || frame.counter % self.mod_fs != 0 {
+            return;
+        }
+
+        let get_fs = deets::get_fs;
+        let mount_points = self.items.iter()
+            .map(|item| item["mount_point"].as_str().unwrap())
+            .collect::<Vec<&str>>();
+        self.usage = timings!("fs_usage", get_fs, mount_points);
+    }
+
+    fn render(&mut self) {
+        self.rows.iter().for_each(|(k, (lbl, bar))| {
+            match self.describe(k) {
+                Some((text, fraction)) => {
+                    lbl.set_text(&text);
+                    update_bar(bar, fraction);
+                },
+                None => (),
+            }
+        });
+    }
+
+    fn text(&self) -> Vec<Line> {
+        let mut lines = Vec::new();
+
+        for item in self.items.iter() {
+            match self.describe(item["mount_point"].as_str().unwrap()) {
+                Some((text, fraction)) => {
+                    lines.push(Line::Row(String::from(item["text"].as_str().unwrap()), text));
+                    lines.push(Line::Bar(fraction));
+                },
+                None => (),
+            }
+        }
+
+        return lines;
+    }
+
+    fn readings(&self) -> Vec<Reading> {
+        return self.items.iter().filter_map(|item| {
+            let mount_point = item["mount_point"].as_str().unwrap();
+            let (text, fraction) = self.describe(mount_point)?;
+            return Some(Reading {
+                id:       format!("fs.{}", mount_point),
+                label:    String::from(item["text"].as_str().unwrap()),
+                text:     text,
+                fraction: Some(fraction),
+                level:    None,
+            });
+        }).collect();
+    }
+}
@@ -0,0 +1,192 @@
+use gtk::prelude::*;
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use yaml_rust::Yaml;
+
+use crate::modules::{self, Level};
+
+#[derive(Clone, Copy)]
+pub enum Scale {
+    // 0 to the given max, colored like the bars
+    Fixed(f64),
+    // 0 to the biggest value in the history
+    Auto,
+    // like auto but on a log scale, so small values don't disappear next to a spike
+    Log,
+}
+
+// the settings every graph is drawn with
+#[derive(Clone, Copy)]
+pub struct Style {
+    low:        gdk::RGBA,
+    med:        gdk::RGBA,
+    high:       gdk::RGBA,
+    pub height: i32,
+}
+
+pub struct Graph {
+    area:    gtk::DrawingArea,
+    // one history per series, two are drawn mirrored around the middle
+    history: Rc<RefCell<Vec<VecDeque<f64>>>>,
+    length:  usize,
+    // the one from the config, if any
+    chosen:  Option<Scale>,
+    scale:   Rc<Cell<Scale>>,
+}
+
+fn color(value: Option<&str>, fallback: &str) -> gdk::RGBA {
+    return match value.unwrap_or(fallback).parse() {
+        Ok(c)  => c,
+        Err(_) => fallback.parse().unwrap(),
+    };
+}
+
+pub fn style(settings: &Yaml) -> Style {
+    return Style {
+        low:    color(settings["color_bar"].as_str(),      "#e1eeff"),
+        med:    color(settings["color_bar_med"].as_str(),  "#ffeeaa"),
+        high:   color(settings["color_bar_high"].as_str(), "#ffaaaa"),
+        height: settings["graph_height"].as_i64().unwrap_or(30) as i32,
+    };
+}
+
+impl Style {
+    // the bar color for this usage
+    pub fn color(&self, fraction: f64) -> gdk::RGBA {
+        return match modules::level(fraction) {
+            Level::High => self.high,
+            Level::Med  => self.med,
+            Level::Low  => self.low,
+        };
+    }
+}
+
+// `history`, `scale` and `max` come from the item (or module) the graph is for
+pub fn new(item: &Yaml, style: &Style) -> Graph {
+    return with_series(item, style, 1);
+}
+
+// up and down (or read and write) in one graph, the first above the middle
+pub fn dual(item: &Yaml, style: &Style) -> Graph {
+    return with_series(item, style, 2);
+}
+
+// the top of a `fixed` graph, `None` when the item doesn't set one
+pub fn max(item: &Yaml) -> Option<f64> {
+    return match (item["max"].as_f64(), item["max"].as_i64()) {
+        (Some(m), _) => Some(m),
+        (_, Some(m)) => Some(m as f64),
+        _ => None,
+    };
+}
+
+fn with_series(item: &Yaml, style: &Style, count: usize) -> Graph {
+    let max = max(item).unwrap_or(1.0);
+
+    let chosen = match item["scale"].as_str() {
+        Some("fixed") => Some(Scale::Fixed(max)),
+        Some("auto")  => Some(Scale::Auto),
+        Some("log")   => Some(Scale::Log),
+        _ => None,
+    };
+    let scale = Rc::new(Cell::new(chosen.unwrap_or(Scale::Auto)));
+
+    let length = item["history"].as_i64().unwrap_or(60).max(2) as usize;
+    let history = Rc::new(RefCell::new((0..count).map(|_| VecDeque::with_capacity(length)).collect::<Vec<_>>()));
+
+    let area = gtk::DrawingArea::new();
+    area.set_hexpand(true);
+    area.set_size_request(-1, style.height);
+    area.get_style_context().add_class("graph");
+
+    let style = *style;
+    let samples = history.clone();
+    let current = scale.clone();
+    area.connect_draw(move |area, cr| {
+        let history = samples.borrow();
+        let scale = current.get();
+        let width = area.get_allocated_width() as f64;
+        let height = area.get_allocated_height() as f64;
+
+        let top = match scale {
+            Scale::Fixed(m) => m,
+            Scale::Auto | Scale::Log => history.iter().flatten().cloned().fold(0.0, f64::max),
+        };
+
+        if top <= 0.0 {
+            return gtk::Inhibit(false);
+        }
+
+        // one column per sample, the newest on the right
+        let step = width / length as f64;
+        let band = height / history.len() as f64;
+
+        for (n, series) in history.iter().enumerate() {
+            let offset = length - series.len();
+
+            for (i, val) in series.iter().enumerate() {
+                let fraction = match scale {
+                    Scale::Log => (1.0 + val).ln() / (1.0 + top).ln(),
+                    _ => val / top,
+                }.max(0.0).min(1.0);
+
+                let c = match scale {
+                    Scale::Fixed(_) => style.color(fraction),
+                    _ => style.low,
+                };
+
+                // the first series grows up from the bottom of its band, the second down from the top of its own
+                let y = match n {
+                    0 => band * (1.0 - fraction),
+                    _ => band * n as f64,
+                };
+
+                cr.set_source_rgba(c.red, c.green, c.blue, c.alpha);
+                cr.rectangle((offset + i) as f64 * step, y, step.ceil(), band * fraction);
+                cr.fill();
+            }
+        }
+
+        return gtk::Inhibit(false);
+    });
+
+    return Graph {
+        area:    area,
+        history: history,
+        length:  length,
+        chosen:  chosen,
+        scale:   scale,
+    };
+}
+
+impl Graph {
+    pub fn widget(&self) -> &gtk::DrawingArea {
+        return &self.area;
+    }
+
+    // `fallback` is the scale to use when the config doesn't pick one
+    pub fn push(&self, val: f64, fallback: Scale) {
+        self.push_all(&[val], fallback);
+    }
+
+    pub fn push_dual(&self, first: f64, second: f64, fallback: Scale) {
+        self.push_all(&[first, second], fallback);
+    }
+
+    fn push_all(&self, vals: &[f64], fallback: Scale) {
+        self.scale.set(self.chosen.unwrap_or(fallback));
+
+        for (series, val) in self.history.borrow_mut().iter_mut().zip(vals.iter()) {
+            if series.len() == self.length {
+                series.pop_front();
+            }
+
+            series.push_back(*val);
+        }
+
+        self.area.queue_draw();
+    }
+}
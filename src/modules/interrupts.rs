@@ -0,0 +1,130 @@
+use gtk::prelude::*;
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use yaml_rust::Yaml;
+
+use crate::deets;
+use crate::modules::{Frame, Line, Module, Reading, SPACING, Spec};
+
+// `softirqs: true` ranks the lines of /proc/softirqs along with the hardware ones
+pub const SPEC: Spec = Spec {
+    about:         "top `limit` interrupts by rate, from /proc/interrupts",
+    keys:          &["limit", "softirqs"],
+    required:      &[],
+    item_keys:     &[],
+    item_required: &[],
+};
+
+struct Row {
+    key: gtk::Label,
+    val: gtk::Label,
+}
+
+struct Rate {
+    id:    String,
+    label: String,
+    rate:  f64,
+}
+
+pub struct Interrupts {
+    limit:    usize,
+    softirqs: bool,
+    rows:     Vec<Row>,
+    last:     HashMap<String, u64>,
+    last_instant: Instant,
+    top:      Vec<Rate>,
+}
+
+pub fn new(item: &Yaml, _settings: &Yaml) -> Box<dyn Module> {
+    return Box::new(Interrupts {
+        limit:    item["limit"].as_i64().unwrap_or(5).max(0) as usize,
+        softirqs: item["softirqs"].as_bool().unwrap_or(false),
+        rows:     Vec::new(),
+        last:     HashMap::new(),
+        last_instant: Instant::now(),
+        top:      Vec::new(),
+    });
+}
+
+impl Module for Interrupts {
+    fn build(&mut self, container: &gtk::Box) {
+        container.set_orientation(gtk::Orientation::Vertical);
+
+        for _ in 0..self.limit {
+            let line_box = gtk::Box::new(gtk::Orientation::Horizontal, SPACING);
+            line_box.get_style_context().add_class("row");
+
+            let key = gtk::Label::new(None);
+            key.get_style_context().add_class("key");
+
+            let val = gtk::Label::new(None);
+            val.set_halign(gtk::Align::End);
+            val.get_style_context().add_class("val");
+
+            line_box.add(&key);
+            line_box.pack_start(&val, true, true, 0);
+            container.add(&line_box);
+
+            self.rows.push(Row { key: key, val: val });
+        }
+    }
+
+    fn sample(&mut self, _frame: &Frame) {
+        let secs = self.last_instant.elapsed().as_secs_f64();
+        self.last_instant = Instant::now();
+
+        let interrupts = deets::get_interrupts(self.softirqs);
+        let mut rates: Vec<Rate> = interrupts.iter().filter_map(|irq| {
+            let last = *self.last.get(&irq.id)?;
+            return match secs > 0.0 {
+                true => Some(Rate {
+                    id:    irq.id.clone(),
+                    label: irq.label.clone(),
+                    rate:  irq.total.saturating_sub(last) as f64 / secs,
+                }),
+                false => None,
+            };
+        }).collect();
+
+        self.last = interrupts.into_iter().map(|irq| (irq.id, irq.total)).collect();
+
+        rates.sort_by(|a, b| b.rate.partial_cmp(&a.rate).unwrap());
+        rates.truncate(self.limit);
+        self.top = rates;
+    }
+
+    fn render(&mut self) {
+        self.rows.iter().enumerate().for_each(|(i, row)| {
+            match self.top.get(i) {
+                Some(irq) => {
+                    row.key.set_text(&irq.label);
+                    row.val.set_text(&format!("{:.0}/s", irq.rate));
+                },
+                None => {
+                    row.key.set_text("");
+                    row.val.set_text("");
+                },
+            }
+        });
+    }
+
+    fn text(&self) -> Vec<Line> {
+        return self.top.iter()
+            .map(|irq| Line::Row(irq.label.clone(), format!("{:.0}/s", irq.rate)))
+            .collect();
+    }
+
+    fn readings(&self) -> Vec<Reading> {
+        return self.top.iter().map(|irq| {
+            return Reading {
+                id:       format!("irq.{}", irq.id),
+                label:    irq.label.clone(),
+                text:     format!("{:.0}/s", irq.rate),
+                fraction: None,
+                level:    None,
+            };
+        }).collect();
+    }
+}
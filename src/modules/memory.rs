@@ -0,0 +1,238 @@
+use gtk::prelude::*;
+
+use yaml_rust::Yaml;
+
+use crate::deets::{self, CacheWants, FrameCache};
+use crate::format;
+use crate::modules::{Frame, Line, Module, Reading, SPACING, Spec, update_bar};
+
+// `details` is a list of /proc/meminfo fields (Buffers, Cached, Shmem, Dirty...)
+pub const SPEC: Spec = Spec {
+    about:         "RAM, swap and zram usage plus any /proc/meminfo fields",
+    keys:          &["used", "swap", "zram", "details"],
+    required:      &[],
+    item_keys:     &[],
+    item_required: &[],
+};
+
+#[derive(Clone, Copy)]
+enum Used {
+    // MemTotal - MemAvailable, what can't be had without swapping
+    Available,
+    // MemTotal - MemFree, counts the page cache as used
+    Free,
+    // MemTotal - MemFree - Buffers - Cached - SReclaimable, like `free` and htop
+    Cache,
+}
+
+enum Entry {
+    Ram,
+    Swap,
+    Zram(String),
+    Detail(String),
+}
+
+struct Value {
+    text:     String,
+    fraction: Option<f64>,
+}
+
+struct Row {
+    val: gtk::Label,
+    bar: Option<gtk::ProgressBar>,
+}
+
+pub struct Memory {
+    used:    Used,
+    entries: Vec<Entry>,
+    rows:    Vec<Row>,
+    values:  Vec<Value>,
+}
+
+pub fn new(item: &Yaml, _settings: &Yaml) -> Box<dyn Module> {
+    let mut entries = vec![Entry::Ram];
+
+    if item["swap"].as_bool().unwrap_or(true) {
+        entries.push(Entry::Swap);
+    }
+
+    if item["zram"].as_bool().unwrap_or(true) {
+        entries.extend(deets::get_zram().into_iter().map(|z| Entry::Zram(z.name)));
+    }
+
+    match item["details"].as_vec() {
+        Some(details) => entries.extend(details.iter()
+            .filter_map(|d| d.as_str())
+            .map(|d| Entry::Detail(String::from(d)))),
+        None => (),
+    }
+
+    return Box::new(Memory {
+        used: match item["used"].as_str() {
+            Some("free")  => Used::Free,
+            Some("cache") => Used::Cache,
+            _ => Used::Available,
+        },
+        entries: entries,
+        rows:    Vec::new(),
+        values:  Vec::new(),
+    });
+}
+
+impl Entry {
+    fn label(&self) -> String {
+        return match self {
+            Entry::Ram       => String::from("RAM:"),
+            Entry::Swap      => String::from("Swap:"),
+            Entry::Zram(n)   => format!("{}:", n),
+            Entry::Detail(d) => format!("{}:", d),
+        };
+    }
+
+    fn id(&self) -> String {
+        return match self {
+            Entry::Ram       => String::from("mem.ram"),
+            Entry::Swap      => String::from("mem.swap"),
+            Entry::Zram(n)   => format!("mem.{}", n),
+            Entry::Detail(d) => format!("mem.{}", d.to_lowercase()),
+        };
+    }
+
+    fn has_bar(&self) -> bool {
+        return match self {
+            Entry::Detail(_) => false,
+            _ => true,
+        };
+    }
+}
+
+fn used_of(used: &u64, total: &u64) -> Value {
+    let fraction = match total {
+        0 => 0.0,
+        _ => *used as f64 / *total as f64,
+    };
+
+    return Value {
+        text:     format!("{} / {} {:.0}%", format::size(*used), format::size(*total), fraction * 100.0),
+        fraction: Some(fraction),
+    };
+}
+
+fn ram_used(cache: &FrameCache, used: Used) -> u64 {
+    let total = cache.mem("MemTotal");
+    return match used {
+        Used::Available => total.saturating_sub(cache.mem_available()),
+        Used::Free      => total.saturating_sub(cache.mem("MemFree")),
+        Used::Cache     => total.saturating_sub(cache.mem("MemFree") + cache.mem("Buffers") + cache.mem("Cached") + cache.mem("SReclaimable")),
+    };
+}
+
+impl Module for Memory {
+    fn build(&mut self, container: &gtk::Box) {
+        container.set_orientation(gtk::Orientation::Vertical);
+
+        for entry in self.entries.iter() {
+            let line_box = gtk::Box::new(gtk::Orientation::Horizontal, SPACING);
+            line_box.get_style_context().add_class("row");
+
+            let key = gtk::Label::new(None);
+            key.get_style_context().add_class("key");
+            key.set_text(&entry.label());
+
+            let val = gtk::Label::new(None);
+            val.set_halign(gtk::Align::End);
+            val.get_style_context().add_class("val");
+
+            line_box.add(&key);
+            line_box.pack_start(&val, true, true, 0);
+            container.add(&line_box);
+
+            let bar = match entry.has_bar() {
+                true => {
+                    let progress = gtk::ProgressBar::new();
+                    progress.set_hexpand(true);
+                    progress.set_sensitive(false);
+                    container.add(&progress);
+                    Some(progress)
+                },
+                false => None,
+            };
+
+            self.rows.push(Row { val: val, bar: bar });
+        }
+    }
+
+    fn cache_wants(&self) -> CacheWants {
+        return CacheWants { meminfo: true, ..CacheWants::default() };
+    }
+
+    fn sample(&mut self, frame: &Frame) {
+        let cache = frame.cache;
+        let zram = match self.entries.iter().any(|e| match e { Entry::Zram(_) => true, _ => false }) {
+            true  => deets::get_zram(),
+            false => Vec::new(),
+        };
+
+        self.values = self.entries.iter().map(|entry| {
+            return match entry {
+                Entry::Ram  => used_of(&ram_used(cache, self.used), &cache.mem("MemTotal")),
+                Entry::Swap => used_of(&cache.mem("SwapTotal").saturating_sub(cache.mem("SwapFree")), &cache.mem("SwapTotal")),
+                Entry::Zram(name) => match zram.iter().find(|z| &z.name == name) {
+                    Some(z) => {
+                        let mut value = used_of(&z.orig, &z.disksize);
+                        value.text = format!("{} in {}", format::size(z.orig), format::size(z.compr));
+                        value
+                    },
+                    None => Value { text: String::from("gone"), fraction: None },
+                },
+                // HugePages_* are page counts, everything else is kB
+                Entry::Detail(d) => Value {
+                    text: match (d.starts_with("HugePages_"), cache.meminfo.get(d)) {
+                        (_, None)        => String::from("unknown"),
+                        (true, Some(n))  => format!("{}", n),
+                        (false, Some(n)) => format::size(*n),
+                    },
+                    fraction: None,
+                },
+            };
+        }).collect();
+    }
+
+    fn render(&mut self) {
+        self.rows.iter().zip(self.values.iter()).for_each(|(row, value)| {
+            row.val.set_text(&value.text);
+
+            match (&row.bar, value.fraction) {
+                (Some(bar), Some(f)) => update_bar(bar, f),
+                _ => (),
+            }
+        });
+    }
+
+    fn text(&self) -> Vec<Line> {
+        let mut lines = Vec::new();
+
+        self.entries.iter().zip(self.values.iter()).for_each(|(entry, value)| {
+            lines.push(Line::Row(entry.label(), value.text.clone()));
+
+            match (entry.has_bar(), value.fraction) {
+                (true, Some(f)) => lines.push(Line::Bar(f)),
+                _ => (),
+            }
+        });
+
+        return lines;
+    }
+
+    fn readings(&self) -> Vec<Reading> {
+        return self.entries.iter().zip(self.values.iter()).map(|(entry, value)| {
+            return Reading {
+                id:       entry.id(),
+                label:    entry.label(),
+                text:     value.text.clone(),
+                fraction: value.fraction,
+                level:    None,
+            };
+        }).collect();
+    }
+}
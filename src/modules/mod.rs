@@ -0,0 +1,249 @@
+use gtk::prelude::*;
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use yaml_rust::Yaml;
+
+use crate::deets::{self, CacheWants, FrameCache, PsWants};
+
+mod battery;
+mod consumers;
+mod cpu_times;
+mod cpus;
+mod diskio;
+mod filesystem;
+mod graph;
+mod interrupts;
+mod memory;
+mod net;
+mod pressure;
+mod system;
+
+pub const SPACING: i32 = 3;
+
+lazy_static! {
+    static ref FRAME_COUNT: Mutex<u64> = Mutex::new(0);
+}
+
+pub struct Frame<'a> {
+    pub cache: &'a FrameCache,
+    pub counter: u64,
+    // true when the (expensive) /proc/<pid> walk ran for this frame
+    pub top: bool,
+}
+
+// What a module shows, without any GTK. Used by the headless outputs
+pub enum Line {
+    Row(String, String),
+    Bar(f64),
+    Cols(Vec<String>),
+}
+
+// One configured item's current value, flat and keyed by id.
+// Used by the status bar outputs
+pub struct Reading {
+    pub id: String,
+    pub label: String,
+    pub text: String,
+    pub fraction: Option<f64>,
+    // set where the generic thresholds of `level` don't fit
+    pub level: Option<Level>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Low,
+    Med,
+    High,
+}
+
+// A module owns everything for one `type:` entry in the `ui:` list.
+// It is created from its Yaml node, builds its widgets into the frame
+// main.rs gives it, and then gets sample + render called once per frame.
+// Headless outputs only sample, so GTK work belongs in build and render.
+pub trait Module {
+    fn build(&mut self, container: &gtk::Box);
+    fn sample(&mut self, frame: &Frame);
+    fn render(&mut self);
+
+    fn text(&self) -> Vec<Line> {
+        return Vec::new();
+    }
+
+    fn readings(&self) -> Vec<Reading> {
+        return Vec::new();
+    }
+
+    fn wants_ps(&self) -> bool {
+        return false;
+    }
+
+    // what else to read per process, on top of wants_ps
+    fn ps_wants(&self) -> PsWants {
+        return PsWants::default();
+    }
+
+    // the optional /proc files the module reads from the frame cache
+    fn cache_wants(&self) -> CacheWants {
+        return CacheWants::default();
+    }
+}
+
+// item is the module's node from `ui:`, settings is the top level `settings:`
+pub type Constructor = fn(item: &Yaml, settings: &Yaml) -> Box<dyn Module>;
+
+// The keys a module type understands, used to check configs.
+// `text` and `type` are always allowed on the `ui:` entry itself
+#[derive(Clone, Copy)]
+pub struct Spec {
+    pub about:         &'static str,
+    pub keys:          &'static [&'static str],
+    pub required:      &'static [&'static str],
+    pub item_keys:     &'static [&'static str],
+    pub item_required: &'static [&'static str],
+}
+
+lazy_static! {
+    static ref REGISTRY: Mutex<HashMap<String, (Constructor, Spec)>> = Mutex::new(builtins());
+}
+
+fn builtins() -> HashMap<String, (Constructor, Spec)> {
+    let mut map: HashMap<String, (Constructor, Spec)> = HashMap::new();
+    map.insert(String::from("battery"),       (battery::new,       battery::SPEC));
+    map.insert(String::from("cpus"),          (cpus::new,          cpus::SPEC));
+    map.insert(String::from("cpu_times"),     (cpu_times::new,     cpu_times::SPEC));
+    map.insert(String::from("cpu_consumers"), (consumers::new_cpu, consumers::SPEC));
+    map.insert(String::from("mem_consumers"), (consumers::new_mem, consumers::SPEC));
+    map.insert(String::from("io_consumers"),  (consumers::new_io,  consumers::IO_SPEC));
+    map.insert(String::from("memory"),        (memory::new,        memory::SPEC));
+    map.insert(String::from("diskio"),        (diskio::new,        diskio::SPEC));
+    map.insert(String::from("filesystem"),    (filesystem::new,    filesystem::SPEC));
+    map.insert(String::from("interrupts"),    (interrupts::new,    interrupts::SPEC));
+    map.insert(String::from("net"),           (net::new,           net::SPEC));
+    map.insert(String::from("pressure"),      (pressure::new,      pressure::SPEC));
+    map.insert(String::from("system"),        (system::new,        system::SPEC));
+    return map;
+}
+
+#[allow(dead_code)]
+pub fn register(type_name: &str, constructor: Constructor, spec: Spec) {
+    REGISTRY.lock().unwrap().insert(String::from(type_name), (constructor, spec));
+}
+
+pub fn create(item: &Yaml, settings: &Yaml) -> Option<Box<dyn Module>> {
+    let registry = REGISTRY.lock().unwrap();
+    return match item["type"].as_str() {
+        Some(t) => registry.get(t).map(|(constructor, _)| constructor(item, settings)),
+        None => None,
+    };
+}
+
+// What `columns` of the *_consumers modules can be
+pub fn consumer_columns() -> Vec<&'static str> {
+    return consumers::CHEAP_COLUMNS.iter().chain(consumers::DETAIL_COLUMNS.iter()).cloned().collect();
+}
+
+// Every registered type and its spec, sorted by type
+pub fn specs() -> Vec<(String, Spec)> {
+    let registry = REGISTRY.lock().unwrap();
+    let mut specs: Vec<(String, Spec)> = registry.iter().map(|(k, (_, spec))| (k.clone(), *spec)).collect();
+    specs.sort_by(|a, b| a.0.cmp(&b.0));
+    return specs;
+}
+
+// Creates every module in `ui:` without building any widgets, along with each one's title
+pub fn create_all(ui: &Yaml, settings: &Yaml) -> (Vec<String>, Vec<Box<dyn Module>>) {
+    let mut titles = Vec::new();
+    let mut modules = Vec::new();
+
+    for i in ui.as_vec().unwrap_or(&Vec::new()) {
+        match create(i, settings) {
+            Some(module) => {
+                titles.push(String::from(i["text"].as_str().unwrap_or("")));
+                modules.push(module);
+            },
+            None => (),
+        }
+    }
+
+    return (titles, modules);
+}
+
+// Builds the frame cache and lets every module sample it, rendering is up to the caller
+pub fn sample_all(modules: &mut Vec<Box<dyn Module>>, mod_top: u64) {
+    let mut frame_counter = FRAME_COUNT.lock().unwrap();
+    let wants_ps = modules.iter().any(|m| m.wants_ps());
+    #[cfg(feature = "prometheus")]
+    let wants_ps = wants_ps || crate::metrics::wants_ps();
+    let should_top = wants_ps && *frame_counter % mod_top == 0;
+    let wants = modules.iter().map(|m| m.ps_wants()).fold(PsWants::default(), |a, b| PsWants {
+        io:      a.io || b.io,
+        details: a.details || b.details,
+    });
+    let cache_wants = modules.iter().fold(CacheWants::default(), |a, m| a.or(m.cache_wants()));
+    #[cfg(feature = "prometheus")]
+    let cache_wants = cache_wants.or(crate::metrics::cache_wants());
+    let frame_cache = deets::get_frame_cache(*frame_counter, mod_top, should_top, wants, cache_wants);
+
+    let frame = Frame {
+        cache: &frame_cache,
+        counter: *frame_counter,
+        top: should_top,
+    };
+
+    modules.iter_mut().for_each(|module| module.sample(&frame));
+    crate::ipc::publish(modules);
+
+    #[cfg(feature = "prometheus")]
+    crate::metrics::collect(&frame);
+
+    *frame_counter += 1;
+}
+
+impl Reading {
+    pub fn level(&self) -> Option<Level> {
+        return match self.level {
+            Some(l) => Some(l),
+            None => self.fraction.map(level),
+        };
+    }
+}
+
+pub fn level(fraction: f64) -> Level {
+    return level_at(fraction, 0.50, 0.80);
+}
+
+// for readings where trouble starts somewhere else than with usage
+pub fn level_at(fraction: f64, med: f64, high: f64) -> Level {
+    if fraction > high {
+        return Level::High;
+    } else if fraction > med {
+        return Level::Med;
+    }
+
+    return Level::Low;
+}
+
+pub fn update_bar(bar: &gtk::ProgressBar, fraction: f64) {
+    update_bar_level(bar, fraction, level(fraction));
+}
+
+pub fn update_bar_level(bar: &gtk::ProgressBar, fraction: f64, level: Level) {
+    match level {
+        Level::High => {
+            bar.get_style_context().remove_class("med");
+            bar.get_style_context().add_class("high");
+        },
+        Level::Med => {
+            bar.get_style_context().add_class("med");
+            bar.get_style_context().remove_class("high");
+        },
+        Level::Low => {
+            bar.get_style_context().remove_class("med");
+            bar.get_style_context().remove_class("high");
+        },
+    }
+
+    bar.set_fraction(fraction);
+}
@@ -0,0 +1,281 @@
+use gtk::prelude::*;
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use yaml_rust::Yaml;
+
+use crate::format;
+use crate::modules::{Frame, Line, Module, Reading, SPACING, Spec};
+use crate::modules::graph::{self, Graph, Scale};
+
+pub const SPEC: Spec = Spec {
+    about:         "upload/download rate of the given interfaces",
+    keys:          &["items", "units", "prefix"],
+    required:      &[],
+    item_keys:     &["name", "interface", "totals", "peak", "widget", "history", "scale", "max"],
+    item_required: &["name", "interface"],
+};
+
+struct NetDevCache {
+    first_bytes: (u64, u64),
+    peak: (f64, f64),
+}
+
+lazy_static! {
+    // lives outside the module so a config reload doesn't restart the session totals and peaks
+    static ref NET_DEV_CACHE: Mutex<HashMap<String, NetDevCache>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Copy)]
+enum Stat {
+    Rate,
+    // since randy started
+    Session,
+    // the kernel's counters
+    Boot,
+    Peak,
+}
+
+impl Stat {
+    fn label(&self) -> &'static str {
+        return match self {
+            Stat::Rate    => "",
+            Stat::Session => "Session",
+            Stat::Boot    => "Boot",
+            Stat::Peak    => "Peak",
+        };
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Stats {
+    rate:    (f64, f64),
+    session: (u64, u64),
+    boot:    (u64, u64),
+    peak:    (f64, f64),
+}
+
+impl Stats {
+    fn get(&self, stat: Stat) -> (f64, f64) {
+        return match stat {
+            Stat::Rate    => self.rate,
+            Stat::Session => (self.session.0 as f64, self.session.1 as f64),
+            Stat::Boot    => (self.boot.0 as f64, self.boot.1 as f64),
+            Stat::Peak    => self.peak,
+        };
+    }
+}
+
+struct Row {
+    interface: String,
+    stat: Stat,
+    up: gtk::Label,
+    down: gtk::Label,
+}
+
+pub struct Net {
+    items:  Vec<Yaml>,
+    units:  format::Units,
+    style:  graph::Style,
+    rows:   Vec<Row>,
+    graphs: HashMap<String, Graph>,
+    stats:  HashMap<String, Stats>,
+}
+
+pub fn new(item: &Yaml, settings: &Yaml) -> Box<dyn Module> {
+    return Box::new(Net {
+        items:  item["items"].as_vec().unwrap_or(&Vec::new()).clone(),
+        units:  format::units(item),
+        style:  graph::style(settings),
+        rows:   Vec::new(),
+        graphs: HashMap::new(),
+        stats:  HashMap::new(),
+    });
+}
+
+// the rate row plus whichever of totals/peak the item asks for
+fn item_stats(item: &Yaml) -> Vec<Stat> {
+    let mut stats = vec![Stat::Rate];
+
+    if item["totals"].as_bool().unwrap_or(false) {
+        stats.push(Stat::Session);
+        stats.push(Stat::Boot);
+    }
+
+    if item["peak"].as_bool().unwrap_or(false) {
+        stats.push(Stat::Peak);
+    }
+
+    return stats;
+}
+
+fn add_val(grid: &gtk::Grid, col: i32, row: i32, text: &str) -> gtk::Label {
+    let val_box = gtk::Box::new(gtk::Orientation::Horizontal, SPACING);
+    let lbl = gtk::Label::new(None);
+    lbl.set_halign(gtk::Align::Start);
+    lbl.set_text(text);
+    val_box.add(&lbl);
+
+    let val = gtk::Label::new(None);
+    val.get_style_context().add_class("val");
+    val.set_hexpand(true);
+    val.set_halign(gtk::Align::End);
+    val.set_text("0000.00 KB");
+    val_box.add(&val);
+    val_box.set_halign(gtk::Align::Fill);
+
+    grid.attach(&val_box, col, row, 1, 1);
+    return val;
+}
+
+fn add_net(container: &gtk::Box, items: &Vec<Yaml>, style: &graph::Style, rows: &mut Vec<Row>, graphs: &mut HashMap<String, Graph>) {
+    container.set_orientation(gtk::Orientation::Horizontal);
+    container.get_style_context().add_class("net");
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(SPACING as u32);
+    grid.set_column_spacing(SPACING as u32);
+
+    let mut r = 0;
+    items.iter().for_each(|item| {
+        let interface = item["interface"].as_str().unwrap();
+
+        for stat in item_stats(item) {
+            let key = gtk::Label::new(None);
+            key.get_style_context().add_class("key");
+            key.set_text(&match stat {
+                Stat::Rate => format!("{}:", item["name"].as_str().unwrap()),
+                _ => format!("  {}", stat.label()),
+            });
+            key.set_halign(gtk::Align::Start);
+            key.set_hexpand(true);
+            grid.attach(&key, 0, r, 1, 1);
+
+            rows.push(Row {
+                interface: String::from(interface),
+                stat: stat,
+                up: add_val(&grid, 1, r, "Up"),
+                down: add_val(&grid, 2, r, "Down"),
+            });
+            r += 1;
+        }
+
+        if item["widget"].as_str() == Some("graph") {
+            let graph = graph::dual(item, style);
+            grid.attach(graph.widget(), 0, r, 3, 1);
+            graphs.insert(String::from(interface), graph);
+            r += 1;
+        }
+    });
+
+    container.add(&grid);
+}
+
+impl Module for Net {
+    fn build(&mut self, container: &gtk::Box) {
+        add_net(container, &self.items, &self.style, &mut self.rows, &mut self.graphs);
+    }
+
+    fn sample(&mut self, frame: &Frame) {
+        let cache = &mut NET_DEV_CACHE.lock().unwrap();
+        let stats = &mut self.stats;
+
+        self.items.iter().for_each(|item| {
+            let interface = item["interface"].as_str().unwrap();
+            let (up, down) = match frame.cache.net_dev.get(interface) {
+                Some(counters) => counters,
+                None => return,
+            };
+
+            // the rates come with the frame, the peak only ever goes up so any number of modules can keep it
+            let dev = cache.entry(String::from(interface)).or_insert(NetDevCache {
+                first_bytes: (up.total, down.total),
+                peak: (0.0, 0.0),
+            });
+            dev.peak = (dev.peak.0.max(up.rate), dev.peak.1.max(down.rate));
+
+            stats.insert(String::from(interface), Stats {
+                rate:    (up.rate, down.rate),
+                session: (up.total.saturating_sub(dev.first_bytes.0), down.total.saturating_sub(dev.first_bytes.1)),
+                boot:    (up.total, down.total),
+                peak:    dev.peak,
+            });
+        });
+    }
+
+    fn render(&mut self) {
+        self.rows.iter().for_each(|row| {
+            match self.stats.get(&row.interface) {
+                Some(stats) => {
+                    let (up, down) = stats.get(row.stat);
+                    row.up.set_text(&format::rate(up, &self.units));
+                    row.down.set_text(&format::rate(down, &self.units));
+                },
+                None => (),
+            }
+        });
+
+        self.graphs.iter().for_each(|(interface, graph)| {
+            match self.stats.get(interface) {
+                Some(stats) => graph.push_dual(stats.rate.0, stats.rate.1, Scale::Auto),
+                None => (),
+            }
+        });
+    }
+
+    fn text(&self) -> Vec<Line> {
+        let mut lines = Vec::new();
+
+        for item in self.items.iter() {
+            let stats = match self.stats.get(item["interface"].as_str().unwrap()) {
+                Some(s) => s,
+                None => continue,
+            };
+
+            for stat in item_stats(item) {
+                let (up, down) = stats.get(stat);
+                lines.push(Line::Row(
+                    match stat {
+                        Stat::Rate => format!("{}:", item["name"].as_str().unwrap()),
+                        _ => format!("  {}", stat.label()),
+                    },
+                    format!("Up {:>10}  Down {:>10}", format::rate(up, &self.units), format::rate(down, &self.units))));
+            }
+        }
+
+        return lines;
+    }
+
+    // `net.<interface>` is the rate, the totals and peak get their own ids
+    fn readings(&self) -> Vec<Reading> {
+        let mut readings = Vec::new();
+
+        for item in self.items.iter() {
+            let interface = item["interface"].as_str().unwrap();
+            let stats = match self.stats.get(interface) {
+                Some(s) => s,
+                None => continue,
+            };
+
+            for stat in item_stats(item) {
+                let (up, down) = stats.get(stat);
+                readings.push(Reading {
+                    id: match stat {
+                        Stat::Rate => format!("net.{}", interface),
+                        _ => format!("net.{}.{}", interface, stat.label().to_lowercase()),
+                    },
+                    label: match stat {
+                        Stat::Rate => format!("{}:", item["name"].as_str().unwrap()),
+                        _ => format!("{} {}:", item["name"].as_str().unwrap(), stat.label()),
+                    },
+                    text: format!("Up {} Down {}", format::rate(up, &self.units), format::rate(down, &self.units)),
+                    fraction: None,
+                    level: None,
+                });
+            }
+        }
+
+        return readings;
+    }
+}
@@ -0,0 +1,146 @@
+use gtk::prelude::*;
+
+use yaml_rust::Yaml;
+
+use crate::deets::{self, CacheWants, PressureLine};
+use crate::modules::{self, Frame, Line, Module, Reading, SPACING, Spec, update_bar_level};
+
+// `med` and `high` are the stall % where the bar changes color
+pub const SPEC: Spec = Spec {
+    about:         "pressure stall information (PSI) for cpu, memory and io",
+    keys:          &["resources", "avg", "full", "med", "high"],
+    required:      &[],
+    item_keys:     &[],
+    item_required: &[],
+};
+
+struct Row {
+    val: gtk::Label,
+    bar: gtk::ProgressBar,
+}
+
+pub struct Pressure {
+    resources: Vec<String>,
+    avg:       String,
+    full:      bool,
+    med:       f64,
+    high:      f64,
+    rows:      Vec<Row>,
+    // some and full for each resource, None when the kernel has no PSI for it
+    values:    Vec<Option<(PressureLine, Option<PressureLine>)>>,
+}
+
+fn percent(item: &Yaml, key: &str, fallback: f64) -> f64 {
+    return match (item[key].as_f64(), item[key].as_i64()) {
+        (Some(f), _) => f,
+        (_, Some(i)) => i as f64,
+        _ => fallback,
+    };
+}
+
+pub fn new(item: &Yaml, _settings: &Yaml) -> Box<dyn Module> {
+    let resources = match item["resources"].as_vec() {
+        Some(r) => r.iter().filter_map(|r| r.as_str()).map(String::from).collect(),
+        None => vec![String::from("cpu"), String::from("memory"), String::from("io")],
+    };
+
+    return Box::new(Pressure {
+        resources: resources,
+        avg:       deets::avg_key(item),
+        full:      item["full"].as_bool().unwrap_or(false),
+        med:       percent(item, "med", 5.0),
+        high:      percent(item, "high", 20.0),
+        rows:      Vec::new(),
+        values:    Vec::new(),
+    });
+}
+
+impl Pressure {
+    fn describe(&self, value: &Option<(PressureLine, Option<PressureLine>)>) -> (String, f64) {
+        let (some, full) = match value {
+            Some(v) => v,
+            None => return (String::from("n/a"), 0.0),
+        };
+
+        let some_pct = some.get(&self.avg);
+        let text = match (self.full, full) {
+            (true, Some(f)) => format!("some {:.2}% full {:.2}%", some_pct, f.get(&self.avg)),
+            _ => format!("{:.2}%", some_pct),
+        };
+
+        return (text, some_pct / 100.0);
+    }
+}
+
+impl Module for Pressure {
+    fn build(&mut self, container: &gtk::Box) {
+        container.set_orientation(gtk::Orientation::Vertical);
+
+        for resource in self.resources.iter() {
+            let line_box = gtk::Box::new(gtk::Orientation::Horizontal, SPACING);
+            line_box.get_style_context().add_class("row");
+
+            let key = gtk::Label::new(None);
+            key.get_style_context().add_class("key");
+            key.set_text(&format!("{}:", resource));
+
+            let val = gtk::Label::new(None);
+            val.set_halign(gtk::Align::End);
+            val.get_style_context().add_class("val");
+
+            line_box.add(&key);
+            line_box.pack_start(&val, true, true, 0);
+            container.add(&line_box);
+
+            let bar = gtk::ProgressBar::new();
+            bar.set_hexpand(true);
+            bar.set_sensitive(false);
+            container.add(&bar);
+
+            self.rows.push(Row { val: val, bar: bar });
+        }
+    }
+
+    fn cache_wants(&self) -> CacheWants {
+        return CacheWants { pressure: true, ..CacheWants::default() };
+    }
+
+    fn sample(&mut self, frame: &Frame) {
+        self.values = self.resources.iter().map(|resource| {
+            return frame.cache.pressure.get(resource).map(|p| (p.some, p.full));
+        }).collect();
+    }
+
+    fn render(&mut self) {
+        self.rows.iter().zip(self.values.iter()).for_each(|(row, value)| {
+            let (text, fraction) = self.describe(value);
+            row.val.set_text(&text);
+            update_bar_level(&row.bar, fraction, modules::level_at(fraction, self.med / 100.0, self.high / 100.0));
+        });
+    }
+
+    fn text(&self) -> Vec<Line> {
+        let mut lines = Vec::new();
+
+        self.resources.iter().zip(self.values.iter()).for_each(|(resource, value)| {
+            let (text, fraction) = self.describe(value);
+            lines.push(Line::Row(format!("{}:", resource), text));
+            lines.push(Line::Bar(fraction));
+        });
+
+        return lines;
+    }
+
+    fn readings(&self) -> Vec<Reading> {
+        return self.resources.iter().zip(self.values.iter()).map(|(resource, value)| {
+            let (text, fraction) = self.describe(value);
+            return Reading {
+                id:       format!("pressure.{}", resource),
+                label:    format!("{}:", resource),
+                text:     text,
+                fraction: value.as_ref().map(|_| fraction),
+                level:    value.as_ref().map(|_| modules::level_at(fraction, self.med / 100.0, self.high / 100.0)),
+            };
+        }).collect();
+    }
+}
@@ -0,0 +1,191 @@
+use gtk::prelude::*;
+
+use yaml_rust::Yaml;
+
+use crate::deets::{self, CacheWants};
+use crate::format;
+use crate::modules::{Frame, Line, Module, Reading, SPACING, Spec, update_bar};
+use crate::modules::graph::{self, Graph, Scale};
+
+// the keys each func needs on top of these live in deets::FUNCS
+pub const SPEC: Spec = Spec {
+    about:         "one row per item, each showing a func",
+    keys:          &["items"],
+    required:      &[],
+    item_keys:     &["func", "text", "widget", "val", "precision", "whole", "history", "scale", "max", "id", "sensor_name", "label_name", "idx", "field", "resource", "stall", "avg"],
+    item_required: &["func", "text"],
+};
+
+pub enum Gauge {
+    Bar(gtk::ProgressBar),
+    Graph(Graph),
+}
+
+struct Row {
+    val:   gtk::Label,
+    gauge: Option<Gauge>,
+}
+
+struct Value {
+    text:     String,
+    fraction: Option<f64>,
+    plot:     Option<f64>,
+    // what the graph falls back to when the item doesn't pick a scale
+    scale:    Scale,
+}
+
+pub struct System {
+    items:  Vec<Yaml>,
+    style:  graph::Style,
+    rows:   Vec<Row>,
+    values: Vec<Value>,
+}
+
+pub fn new(item: &Yaml, settings: &Yaml) -> Box<dyn Module> {
+    return Box::new(System {
+        items:  item["items"].as_vec().unwrap_or(&Vec::new()).clone(),
+        style:  graph::style(settings),
+        rows:   Vec::new(),
+        values: Vec::new(),
+    });
+}
+
+// `id:` if the item has one, otherwise the func plus whatever tells
+// two items with the same func apart
+pub fn item_id(item: &Yaml) -> String {
+    match item["id"].as_str() {
+        Some(id) => return String::from(id),
+        None => (),
+    }
+
+    let func = item["func"].as_str().unwrap_or("");
+    return match func {
+        "sensor_info" | "hwmon" => format!("{}.{}", func, item["label_name"].as_str().unwrap_or("")),
+        "nvidia_gpu_temp"       => format!("{}.{}", func, item["idx"].as_i64().unwrap_or(0)),
+        "pressure"              => format!("{}.{}", func, item["resource"].as_str().unwrap_or("")),
+        "cpu_time"              => match item["idx"].as_i64() {
+            Some(idx) => format!("{}.cpu{:02}.{}", func, idx, item["field"].as_str().unwrap_or("")),
+            None => format!("{}.{}", func, item["field"].as_str().unwrap_or("")),
+        },
+        _ => String::from(func),
+    };
+}
+
+pub fn add_standard(item: &Yaml, inner_box: &gtk::Box, style: &graph::Style) -> (gtk::Label, Option<Gauge>) {
+    let line_box = gtk::Box::new(gtk::Orientation::Horizontal, SPACING);
+    line_box.get_style_context().add_class("row");
+
+    let key = gtk::Label::new(None);
+    key.get_style_context().add_class("key");
+    key.set_text(&format!("{}", item["text"].as_str().unwrap()));
+
+    let val = gtk::Label::new(None);
+    val.set_justify(gtk::Justification::Right);
+    val.set_halign(gtk::Align::End);
+    val.get_style_context().add_class("val");
+
+    line_box.add(&key);
+    line_box.pack_start(&val, true, true, 0);
+
+    let mut p = None;
+
+    match item["widget"].as_str() {
+        Some("bar") => {
+            let progress = gtk::ProgressBar::new();
+            progress.set_hexpand(true);
+            progress.set_sensitive(false);
+
+            let vbox = gtk::Box::new(gtk::Orientation::Vertical, SPACING);
+            vbox.add(&line_box);
+            vbox.add(&progress);
+            inner_box.add(&vbox);
+            p = Some(Gauge::Bar(progress));
+        },
+        Some("graph") => {
+            let graph = graph::new(item, style);
+
+            let vbox = gtk::Box::new(gtk::Orientation::Vertical, SPACING);
+            vbox.add(&line_box);
+            vbox.add(graph.widget());
+            inner_box.add(&vbox);
+            p = Some(Gauge::Graph(graph));
+        },
+        _ => {
+            inner_box.add(&line_box);
+        },
+    }
+
+    return (val, p);
+}
+
+impl Module for System {
+    fn build(&mut self, container: &gtk::Box) {
+        for item in self.items.iter() {
+            let (val, gauge) = add_standard(item, container, &self.style);
+            self.rows.push(Row { val: val, gauge: gauge });
+        }
+    }
+
+    fn cache_wants(&self) -> CacheWants {
+        return self.items.iter().fold(CacheWants::default(), |a, item| a.or(deets::func_wants(item["func"].as_str().unwrap_or(""))));
+    }
+
+    fn sample(&mut self, frame: &Frame) {
+        self.values = self.items.iter().map(|item| {
+            let deet = deets::do_func(item, frame.cache);
+            // readings with a fraction are graphed 0 to 1 like the bars,
+            // unless the item sets its own `max`, then they're graphed as a percent against it
+            let (plot, scale) = match (deet.fraction(), graph::max(item)) {
+                (Some(f), None) => (Some(f), Scale::Fixed(1.0)),
+                (Some(_), Some(max)) => (deet.percent(), Scale::Fixed(max)),
+                (None, _) => (deet.value(), Scale::Auto),
+            };
+
+            return Value {
+                text:     format::deet(&deet, item),
+                fraction: deet.fraction(),
+                plot:     plot,
+                scale:    scale,
+            };
+        }).collect();
+    }
+
+    fn render(&mut self) {
+        self.rows.iter().zip(self.values.iter()).for_each(|(row, value)| {
+            row.val.set_text(value.text.as_str());
+
+            match (&row.gauge, value.fraction, value.plot) {
+                (Some(Gauge::Bar(bar)), Some(f), _) => update_bar(bar, f),
+                (Some(Gauge::Graph(graph)), _, Some(v)) => graph.push(v, value.scale),
+                _ => (),
+            }
+        });
+    }
+
+    fn text(&self) -> Vec<Line> {
+        let mut lines = Vec::new();
+
+        self.items.iter().zip(self.values.iter()).for_each(|(item, value)| {
+            lines.push(Line::Row(String::from(item["text"].as_str().unwrap()), value.text.clone()));
+
+            match (item["widget"].as_str(), value.fraction) {
+                (Some("bar"), Some(f)) => lines.push(Line::Bar(f)),
+                _ => (),
+            }
+        });
+
+        return lines;
+    }
+
+    fn readings(&self) -> Vec<Reading> {
+        return self.items.iter().zip(self.values.iter()).map(|(item, value)| {
+            return Reading {
+                id:       item_id(item),
+                label:    String::from(item["text"].as_str().unwrap()),
+                text:     value.text.clone(),
+                fraction: value.fraction,
+                level:    None,
+            };
+        }).collect();
+    }
+}
@@ -0,0 +1,140 @@
+use std::io::Write;
+use std::{io, thread, time};
+
+use yaml_rust::Yaml;
+
+use crate::modules::{self, Level, Line, Module};
+
+struct Palette {
+    label: String,
+    bar:   String,
+    med:   String,
+    high:  String,
+    reset: String,
+}
+
+// "#rgb" or "#rrggbb" to a 24bit ANSI fg color, anything else gets the fallback
+fn ansi_color(color: Option<&str>, fallback: &str) -> String {
+    let hex = match color {
+        Some(c) if c.starts_with('#') => &c[1..],
+        _ => return String::from(fallback),
+    };
+
+    let rgb: Vec<u8> = match hex.len() {
+        3 => hex.chars().filter_map(|c| u8::from_str_radix(&format!("{}{}", c, c), 16).ok()).collect(),
+        6 => (0..3).filter_map(|i| u8::from_str_radix(&hex[(i * 2)..(i * 2 + 2)], 16).ok()).collect(),
+        _ => Vec::new(),
+    };
+
+    if rgb.len() != 3 {
+        return String::from(fallback);
+    }
+
+    return format!("\x1b[38;2;{};{};{}m", rgb[0], rgb[1], rgb[2]);
+}
+
+fn get_palette(settings: &Yaml, ansi: bool) -> Palette {
+    if !ansi {
+        return Palette {
+            label: String::new(),
+            bar:   String::new(),
+            med:   String::new(),
+            high:  String::new(),
+            reset: String::new(),
+        };
+    }
+
+    return Palette {
+        label: ansi_color(settings["color_label"].as_str(),    "\x1b[36m"),
+        bar:   ansi_color(settings["color_bar"].as_str(),      ""),
+        med:   ansi_color(settings["color_bar_med"].as_str(),  "\x1b[33m"),
+        high:  ansi_color(settings["color_bar_high"].as_str(), "\x1b[31m"),
+        reset: String::from("\x1b[0m"),
+    };
+}
+
+fn pad(s: &str, width: usize) -> String {
+    let len = s.chars().count();
+    return match len < width {
+        true  => format!("{}{}", s, " ".repeat(width - len)),
+        false => String::from(s),
+    };
+}
+
+fn lpad(s: &str, width: usize) -> String {
+    let len = s.chars().count();
+    return match len < width {
+        true  => format!("{}{}", " ".repeat(width - len), s),
+        false => String::from(s),
+    };
+}
+
+fn render_line(line: &Line, width: usize, palette: &Palette) -> String {
+    return match line {
+        Line::Row(key, val) => {
+            let key_width = key.chars().count();
+            let val_width = match width > key_width { true => width - key_width, false => 0 };
+            format!("{}{}{}{}", palette.label, key, palette.reset, lpad(val, val_width))
+        },
+        Line::Bar(fraction) => {
+            let inner = width - 2;
+            let fraction = fraction.max(0.0).min(1.0);
+            let filled = (fraction * inner as f64).round() as usize;
+            let color = match modules::level(fraction) {
+                Level::High => &palette.high,
+                Level::Med  => &palette.med,
+                Level::Low  => &palette.bar,
+            };
+
+            format!("[{}{}{}{}]", color, "#".repeat(filled), palette.reset, " ".repeat(inner - filled))
+        },
+        Line::Cols(cols) if cols.is_empty() => String::new(),
+        Line::Cols(cols) => {
+            let rest: String = cols.iter().skip(1).map(|c| lpad(c, 9)).collect();
+            let name_width = width - rest.chars().count().min(width);
+            let name: String = cols[0].chars().take(name_width).collect();
+            format!("{}{}", pad(&name, name_width), rest)
+        },
+    };
+}
+
+fn render(titles: &Vec<String>, modules: &Vec<Box<dyn Module>>, width: usize, palette: &Palette) -> String {
+    let mut out = String::new();
+
+    for (title, module) in titles.iter().zip(modules.iter()) {
+        out.push_str(&format!("{}{}{}\n", palette.label, title, palette.reset));
+        for line in module.text() {
+            out.push_str(&format!("  {}\n", render_line(&line, width, palette)));
+        }
+    }
+
+    return out;
+}
+
+pub fn run(config: &Yaml, ansi: bool) {
+    let settings = &config["settings"];
+    let timeout = settings["timeout"].as_i64().unwrap_or(1) as u64;
+    let mod_top = settings["mod_top"].as_i64().unwrap_or(2) as u64;
+    let width   = settings["tty_width"].as_i64().unwrap_or(40).max(10) as usize;
+    let palette = get_palette(settings, ansi);
+
+    let (titles, mut mods) = modules::create_all(&config["ui"], settings);
+
+    loop {
+        modules::sample_all(&mut mods, mod_top);
+        let frame = render(&titles, &mods, width, &palette);
+
+        let stdout = io::stdout();
+        let mut handle = stdout.lock();
+        let _ = match ansi {
+            true  => write!(handle, "\x1b[H\x1b[2J{}", frame),
+            false => write!(handle, "{}\n", frame),
+        };
+
+        if handle.flush().is_err() {
+            return;
+        }
+
+        thread::sleep(time::Duration::from_secs(timeout));
+    }
+}
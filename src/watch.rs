@@ -0,0 +1,73 @@
+use std::ffi::CString;
+use std::path::Path;
+use std::{fs, mem, thread, time};
+
+use crate::ipc::Command;
+
+// editors tend to fire a few events per save, let them settle
+const SETTLE_TIME: time::Duration = time::Duration::from_millis(250);
+
+// Watches the directory holding the (symlink resolved) config file, since
+// most editors save by writing a new file and renaming it over the old one
+pub fn start(config_path: &str, commands: glib::Sender<Command>) {
+    let real_path = match fs::canonicalize(config_path) {
+        Ok(p)  => p,
+        Err(e) => {
+            eprintln!("Unable to watch {}: {}", config_path, e);
+            return;
+        },
+    };
+
+    let dir = real_path.parent().unwrap_or(Path::new("/")).to_path_buf();
+    let name = match real_path.file_name() {
+        Some(n) => n.to_os_string().into_string().unwrap_or_default(),
+        None => return,
+    };
+
+    let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+    if fd < 0 {
+        eprintln!("Unable to watch {}: inotify_init1 failed", config_path);
+        return;
+    }
+
+    let c_dir = CString::new(dir.to_string_lossy().as_bytes()).unwrap();
+    let mask = libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO | libc::IN_CREATE;
+    if unsafe { libc::inotify_add_watch(fd, c_dir.as_ptr(), mask) } < 0 {
+        eprintln!("Unable to watch {}: inotify_add_watch failed", dir.display());
+        unsafe { libc::close(fd) };
+        return;
+    }
+
+    thread::spawn(move || {
+        let header = mem::size_of::<libc::inotify_event>();
+        let mut buffer = [0u8; 4096];
+
+        loop {
+            let len = unsafe { libc::read(fd, buffer.as_mut_ptr() as *mut libc::c_void, buffer.len()) };
+            if len <= 0 {
+                return;
+            }
+
+            let mut offset = 0;
+            let mut changed = false;
+            while offset + header <= len as usize {
+                let event: libc::inotify_event = unsafe { std::ptr::read_unaligned(buffer[offset..].as_ptr() as *const libc::inotify_event) };
+                let name_bytes = &buffer[(offset + header)..(offset + header + event.len as usize)];
+                let end = name_bytes.iter().position(|&b| b == 0).unwrap_or(name_bytes.len());
+
+                if &name_bytes[..end] == name.as_bytes() {
+                    changed = true;
+                }
+
+                offset += header + event.len as usize;
+            }
+
+            if changed {
+                thread::sleep(SETTLE_TIME);
+                if commands.send(Command::Reload).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,74 @@
+use std::io::Write;
+use std::{io, process, thread, time};
+
+use yaml_rust::Yaml;
+
+use crate::format::json_string;
+use crate::modules::{self, Level, Reading};
+
+fn line(readings: &Vec<&Reading>) -> String {
+    let text = readings.iter().map(|r| r.text.as_str()).collect::<Vec<&str>>().join(" ");
+    let tooltip = readings.iter().map(|r| format!("{} {}", r.label, r.text)).collect::<Vec<String>>().join("\n");
+    let fraction = readings.iter().filter_map(|r| r.fraction).fold(None, |a: Option<f64>, f| Some(a.unwrap_or(f).max(f)));
+    let level = readings.iter().filter_map(|r| r.level()).max();
+
+    let mut ret = format!("{{\"text\":{},\"tooltip\":{}", json_string(&text), json_string(&tooltip));
+
+    match level {
+        Some(Level::Med)  => ret.push_str(",\"class\":\"med\""),
+        Some(Level::High) => ret.push_str(",\"class\":\"high\""),
+        _ => (),
+    }
+
+    match fraction {
+        Some(f) => ret.push_str(&format!(",\"percentage\":{:.0}", f * 100.0)),
+        None => (),
+    }
+
+    ret.push('}');
+    return ret;
+}
+
+// ids is a comma separated list of item ids, they all end up in one line
+pub fn run(config: &Yaml, ids: &str) {
+    let settings = &config["settings"];
+    let timeout = settings["timeout"].as_i64().unwrap_or(1) as u64;
+    let mod_top = settings["mod_top"].as_i64().unwrap_or(2) as u64;
+    let wanted: Vec<&str> = ids.split(',').filter(|s| !s.is_empty()).collect();
+
+    let (_, mut mods) = modules::create_all(&config["ui"], settings);
+
+    // one frame up front so there is something to check the ids against
+    modules::sample_all(&mut mods, mod_top);
+    let known: Vec<String> = mods.iter().flat_map(|m| m.readings()).map(|r| r.id).collect();
+    let missing: Vec<&&str> = wanted.iter().filter(|id| !known.iter().any(|k| k == *id)).collect();
+
+    if wanted.len() == 0 {
+        eprintln!("No item ids given, available ids:\n  {}", known.join("\n  "));
+        process::exit(1);
+    }
+
+    // interrupt top lists, interfaces that come and go and late PSI files only show up in some frames,
+    // those ids are left out of the line until they are there
+    if missing.len() != 0 {
+        eprintln!("Not there yet: {:?}", missing);
+        eprintln!("Available ids:\n  {}", known.join("\n  "));
+    }
+
+    let stdout = io::stdout();
+    loop {
+        let readings: Vec<Reading> = mods.iter().flat_map(|m| m.readings()).collect();
+        let selected: Vec<&Reading> = wanted.iter()
+            .filter_map(|id| readings.iter().find(|r| &r.id == id))
+            .collect();
+
+        let mut handle = stdout.lock();
+        if writeln!(handle, "{}", line(&selected)).is_err() || handle.flush().is_err() {
+            return;
+        }
+
+        drop(handle);
+        thread::sleep(time::Duration::from_secs(timeout));
+        modules::sample_all(&mut mods, mod_top);
+    }
+}